@@ -1,3 +1,5 @@
+use std::ops::Mul;
+
 pub struct Mat4 {
     data: [[f32; 4]; 4],
 }
@@ -14,6 +16,18 @@ impl Mat4 {
         }
     }
 
+    pub fn rotation_y(angle: f32) -> Self {
+        let (sin, cos) = angle.sin_cos();
+        Mat4 {
+            data: [
+                [cos, 0.0, -sin, 0.0],
+                [0.0, 1.0, 0.0, 0.0],
+                [sin, 0.0, cos, 0.0],
+                [0.0, 0.0, 0.0, 1.0],
+            ],
+        }
+    }
+
     pub fn new(data: [[f32; 4]; 4]) -> Self {
         Mat4 { data }
     }
@@ -21,4 +35,28 @@ impl Mat4 {
     pub fn as_ptr(&self) -> *const f32 {
         self.data.as_ptr() as *const f32
     }
+
+    // Matrices are column-major, as OpenGL expects them.
+    pub fn row(&self, index: usize) -> [f32; 4] {
+        [
+            self.data[0][index],
+            self.data[1][index],
+            self.data[2][index],
+            self.data[3][index],
+        ]
+    }
+}
+
+impl Mul for &Mat4 {
+    type Output = Mat4;
+
+    fn mul(self, other: &Mat4) -> Mat4 {
+        let mut data = [[0.0; 4]; 4];
+        for (col, column) in data.iter_mut().enumerate() {
+            for (row, value) in column.iter_mut().enumerate() {
+                *value = (0..4).map(|k| self.data[k][row] * other.data[col][k]).sum();
+            }
+        }
+        Mat4 { data }
+    }
 }
@@ -1,2 +1,3 @@
 pub mod mat4;
+pub mod quat;
 pub mod vec3;
@@ -0,0 +1,88 @@
+use crate::algebra::vec3::Vec3;
+
+#[derive(Clone, Copy)]
+pub struct Quat {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    pub w: f32,
+}
+
+impl Quat {
+    pub fn from_axis_angle(axis: Vec3, angle: f32) -> Self {
+        let axis = axis.normalize();
+        let (sin, cos) = (angle / 2.0).sin_cos();
+        Quat {
+            x: axis.x * sin,
+            y: axis.y * sin,
+            z: axis.z * sin,
+            w: cos,
+        }
+    }
+
+    // Orientation of a camera looking along +x rotated by yaw around y then pitch, in degrees.
+    pub fn from_yaw_pitch(yaw: f32, pitch: f32) -> Self {
+        let yaw = Quat::from_axis_angle(Vec3::new(0.0, 1.0, 0.0), -yaw.to_radians());
+        let pitch = Quat::from_axis_angle(Vec3::new(0.0, 0.0, 1.0), pitch.to_radians());
+        yaw.mul(&pitch)
+    }
+
+    pub fn mul(&self, other: &Quat) -> Quat {
+        Quat {
+            x: self.w * other.x + self.x * other.w + self.y * other.z - self.z * other.y,
+            y: self.w * other.y - self.x * other.z + self.y * other.w + self.z * other.x,
+            z: self.w * other.z + self.x * other.y - self.y * other.x + self.z * other.w,
+            w: self.w * other.w - self.x * other.x - self.y * other.y - self.z * other.z,
+        }
+    }
+
+    pub fn dot(&self, other: &Quat) -> f32 {
+        self.x * other.x + self.y * other.y + self.z * other.z + self.w * other.w
+    }
+
+    pub fn normalize(&self) -> Quat {
+        let len = self.dot(self).sqrt();
+        Quat {
+            x: self.x / len,
+            y: self.y / len,
+            z: self.z / len,
+            w: self.w / len,
+        }
+    }
+
+    pub fn rotate(&self, v: Vec3) -> Vec3 {
+        let u = Vec3::new(self.x, self.y, self.z);
+        let t = u.cross(&v) * 2.0;
+        v + t * self.w + u.cross(&t)
+    }
+
+    pub fn slerp(&self, other: &Quat, t: f32) -> Quat {
+        let mut cos = self.dot(other);
+        let mut other = *other;
+        if cos < 0.0 {
+            cos = -cos;
+            other = Quat {
+                x: -other.x,
+                y: -other.y,
+                z: -other.z,
+                w: -other.w,
+            };
+        }
+
+        let (a, b) = if cos > 0.9995 {
+            (1.0 - t, t)
+        } else {
+            let angle = cos.acos();
+            let sin = angle.sin();
+            (((1.0 - t) * angle).sin() / sin, (t * angle).sin() / sin)
+        };
+
+        Quat {
+            x: self.x * a + other.x * b,
+            y: self.y * a + other.y * b,
+            z: self.z * a + other.z * b,
+            w: self.w * a + other.w * b,
+        }
+        .normalize()
+    }
+}
@@ -20,8 +20,20 @@ impl Vec3 {
         }
     }
 
+    pub fn dot(&self, other: &Vec3) -> f32 {
+        self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    pub fn length(&self) -> f32 {
+        self.dot(self).sqrt()
+    }
+
+    pub fn lerp(&self, other: &Vec3, t: f32) -> Vec3 {
+        *self + (*other - *self) * t
+    }
+
     pub fn normalize(&self) -> Vec3 {
-        let len = (self.x * self.x + self.y * self.y + self.z * self.z).sqrt();
+        let len = self.length();
         Vec3 {
             x: self.x / len,
             y: self.y / len,
@@ -0,0 +1,11 @@
+use std::env;
+use std::path::PathBuf;
+
+// Per-user settings directory, `$XDG_CONFIG_HOME/earth-gl` or `~/.config/earth-gl`.
+pub fn config_dir() -> PathBuf {
+    env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("earth-gl")
+}
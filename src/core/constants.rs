@@ -1,2 +1,13 @@
 pub const WIDTH: u32 = 1080;
 pub const HEIGHT: u32 = 720;
+pub const GLOBE_RADIUS: f32 = 1.0;
+// One scene unit is the Earth's mean radius.
+pub const METERS_PER_UNIT: f32 = 6_371_000.0;
+pub const MIN_NEAR_PLANE: f32 = 1.0 / METERS_PER_UNIT;
+pub const FAR_PLANE: f32 = 100_000_000.0 / METERS_PER_UNIT;
+pub const DAY_TEXTURE: &str = "assets/earth_day.jpg";
+pub const NIGHT_LIGHTS_TEXTURE: &str = "assets/earth_night.jpg";
+pub const STAR_CATALOGUES: [&str; 2] = ["assets/bsc5.dat", "assets/bsc5.csv"];
+pub const WATER_MASK_TEXTURE: &str = "assets/earth_water_mask.png";
+pub const WATER_NORMALS_TEXTURE: &str = "assets/water_normals.png";
+pub const CLOUDS_TEXTURE: &str = "assets/earth_clouds.jpg";
@@ -0,0 +1,57 @@
+use crate::textures::image::ImageData;
+
+// Largest possible `yiq_distance`, between black and white.
+const MAX_DISTANCE: f32 = 35215.0;
+
+pub struct Comparison {
+    pub differing: usize,
+    pub total: usize,
+    // The reference faded towards white, with differing pixels in red.
+    pub diff: ImageData,
+}
+
+// Counts the pixels whose colour differs noticeably, ignoring alpha. `threshold` is the
+// perceived difference a pixel may have, from 0 (exact) to 1 (anything passes).
+pub fn compare(reference: &ImageData, actual: &ImageData, threshold: f32) -> Comparison {
+    let limit = MAX_DISTANCE * threshold * threshold;
+    let mut differing = 0;
+    let mut diff = Vec::with_capacity(reference.pixels.len());
+
+    for (expected, found) in reference
+        .pixels
+        .chunks_exact(4)
+        .zip(actual.pixels.chunks_exact(4))
+    {
+        if yiq_distance(expected, found) > limit {
+            differing += 1;
+            diff.extend([255, 0, 0, 255]);
+        } else {
+            let luma = 0.299 * expected[0] as f32
+                + 0.587 * expected[1] as f32
+                + 0.114 * expected[2] as f32;
+            let faded = (255.0 + (luma - 255.0) * 0.1) as u8;
+            diff.extend([faded, faded, faded, 255]);
+        }
+    }
+
+    Comparison {
+        differing,
+        total: reference.pixels.len() / 4,
+        diff: ImageData {
+            width: reference.width,
+            height: reference.height,
+            pixels: diff,
+            srgb: true,
+        },
+    }
+}
+
+// Squared colour difference weighted by how visible it is in YIQ space, after Kotsarenko
+// and Ramos, "Measuring perceived color difference using YIQ NTSC transmission color space".
+fn yiq_distance(a: &[u8], b: &[u8]) -> f32 {
+    let [r, g, b] = [0, 1, 2].map(|channel| a[channel] as f32 - b[channel] as f32);
+    let y = r * 0.2988953 + g * 0.5866225 + b * 0.11448223;
+    let i = r * 0.59597799 - g * 0.2741761 - b * 0.3218019;
+    let q = r * 0.21147017 - g * 0.5226171 + b * 0.31114694;
+    0.5053 * y * y + 0.299 * i * i + 0.1957 * q * q
+}
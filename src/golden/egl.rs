@@ -0,0 +1,111 @@
+use khronos_egl as egl;
+
+// EGL_PLATFORM_SURFACELESS_MESA, a display without a window system.
+const PLATFORM_SURFACELESS: egl::Enum = 0x31DD;
+
+// A current OpenGL 3.3 core context drawing into an offscreen pbuffer, which stands in for
+// the window's framebuffer. Loads libEGL at runtime; with Mesa, `LIBGL_ALWAYS_SOFTWARE=1`
+// selects llvmpipe on machines that have a GPU.
+pub struct HeadlessContext {
+    egl: egl::DynamicInstance<egl::EGL1_5>,
+    display: egl::Display,
+    context: egl::Context,
+    surface: egl::Surface,
+}
+
+impl HeadlessContext {
+    pub fn new(width: u32, height: u32) -> Result<Self, String> {
+        let egl = unsafe { egl::DynamicInstance::<egl::EGL1_5>::load_required() }
+            .map_err(|err| format!("could not load libEGL: {}", err))?;
+        let display = unsafe {
+            egl.get_platform_display(
+                PLATFORM_SURFACELESS,
+                egl::DEFAULT_DISPLAY,
+                &[egl::ATTRIB_NONE],
+            )
+        }
+        .map_err(|err| format!("no surfaceless EGL display: {}", err))?;
+        egl.initialize(display)
+            .map_err(|err| format!("could not initialize EGL: {}", err))?;
+
+        let config = egl
+            .choose_first_config(
+                display,
+                &[
+                    egl::SURFACE_TYPE,
+                    egl::PBUFFER_BIT,
+                    egl::RENDERABLE_TYPE,
+                    egl::OPENGL_BIT,
+                    egl::RED_SIZE,
+                    8,
+                    egl::GREEN_SIZE,
+                    8,
+                    egl::BLUE_SIZE,
+                    8,
+                    egl::ALPHA_SIZE,
+                    8,
+                    egl::DEPTH_SIZE,
+                    24,
+                    egl::NONE,
+                ],
+            )
+            .ok()
+            .flatten()
+            .ok_or("no EGL config renders OpenGL into a pbuffer")?;
+
+        egl.bind_api(egl::OPENGL_API)
+            .map_err(|err| format!("no OpenGL support in EGL: {}", err))?;
+        let context = egl
+            .create_context(
+                display,
+                config,
+                None,
+                &[
+                    egl::CONTEXT_MAJOR_VERSION,
+                    3,
+                    egl::CONTEXT_MINOR_VERSION,
+                    3,
+                    egl::CONTEXT_OPENGL_PROFILE_MASK,
+                    egl::CONTEXT_OPENGL_CORE_PROFILE_BIT,
+                    egl::NONE,
+                ],
+            )
+            .map_err(|err| format!("no OpenGL 3.3 core context: {}", err))?;
+        let surface = egl
+            .create_pbuffer_surface(
+                display,
+                config,
+                &[
+                    egl::WIDTH,
+                    width as i32,
+                    egl::HEIGHT,
+                    height as i32,
+                    egl::NONE,
+                ],
+            )
+            .map_err(|err| format!("could not create a pbuffer: {}", err))?;
+        egl.make_current(display, Some(surface), Some(surface), Some(context))
+            .map_err(|err| format!("could not make the context current: {}", err))?;
+
+        gl::load_with(|symbol| {
+            egl.get_proc_address(symbol)
+                .map_or(std::ptr::null(), |function| function as *const _)
+        });
+
+        Ok(HeadlessContext {
+            egl,
+            display,
+            context,
+            surface,
+        })
+    }
+}
+
+impl Drop for HeadlessContext {
+    fn drop(&mut self) {
+        let _ = self.egl.make_current(self.display, None, None, None);
+        let _ = self.egl.destroy_surface(self.display, self.surface);
+        let _ = self.egl.destroy_context(self.display, self.context);
+        let _ = self.egl.terminate(self.display);
+    }
+}
@@ -0,0 +1,201 @@
+// Golden-image tests: fixed scenes rendered from fixed camera poses and compared with the
+// reference PNGs in tests/golden/<backend>. A failing case leaves `<case>.actual.png` and
+// `<case>.diff.png` next to its reference. Run with `EARTH_GL_BLESS=1` to write the current
+// renders as the new references.
+
+mod compare;
+mod egl;
+
+use crate::algebra::vec3::Vec3;
+use crate::core::constants::{HEIGHT, WIDTH};
+use crate::render::backend::opengl::GlBackend;
+use crate::render::backend::software::SoftwareBackend;
+use crate::render::backend::RenderBackend;
+use crate::scene::camera::{CameraMode, ViewState};
+use crate::scene::core::Scene;
+use crate::textures::image::ImageData;
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use self::compare::compare;
+use self::egl::HeadlessContext;
+
+const BLESS_VAR: &str = "EARTH_GL_BLESS";
+// Equinox noon over Greenwich, the sun straight above longitude 0.
+const DATE: &str = "2024-03-20T12:00:00";
+// Perceived difference a pixel may show before it counts as changed, see compare().
+const PIXEL_THRESHOLD: f32 = 0.1;
+// Share of changed pixels tolerated, for rasterization and driver differences on edges.
+const MAX_CHANGED: f32 = 0.002;
+
+struct Case {
+    name: &'static str,
+    view: ViewState,
+    wireframe: bool,
+    // Draws the globe with `test_pattern()` instead of its plain colour.
+    textured: bool,
+}
+
+fn cases() -> [Case; 3] {
+    // Both spheres from main.rs, the small one up and to the right of the globe.
+    let spheres = ViewState {
+        position: Vec3::new(0.6, 0.9, 4.2),
+        yaw: -93.0,
+        pitch: -7.0,
+        fov: 45.0,
+        mode: CameraMode::Free,
+    };
+    [
+        Case {
+            name: "two_spheres",
+            view: spheres,
+            wireframe: false,
+            textured: false,
+        },
+        Case {
+            name: "wireframe",
+            view: spheres,
+            wireframe: true,
+            textured: false,
+        },
+        Case {
+            name: "textured_globe",
+            view: ViewState {
+                position: Vec3::new(-2.0, 1.0, 1.8),
+                yaw: -42.0,
+                pitch: -20.4,
+                fov: 45.0,
+                mode: CameraMode::Free,
+            },
+            wireframe: false,
+            textured: true,
+        },
+    ]
+}
+
+// A 30 degree grid with the prime meridian in red and the equator in yellow, so a flipped or
+// shifted mapping shows.
+fn test_pattern() -> ImageData {
+    let (width, height) = (360, 180);
+    let mut pixels = Vec::with_capacity(width * height * 4);
+    for y in 0..height {
+        for x in 0..width {
+            // Longitude 0 is the middle of the image.
+            let lon = x as i32 - 180;
+            let lat = 90 - y as i32;
+            let color = if (-2..2).contains(&lon) {
+                [220, 30, 30]
+            } else if (-1..1).contains(&lat) {
+                [230, 200, 40]
+            } else if (lon.div_euclid(30) + lat.div_euclid(30)) % 2 == 0 {
+                [40, 90, 160]
+            } else {
+                [200, 210, 220]
+            };
+            pixels.extend(color);
+            pixels.push(255);
+        }
+    }
+    ImageData {
+        width: width as u32,
+        height: height as u32,
+        pixels,
+        srgb: true,
+    }
+}
+
+fn render(backend: Box<dyn RenderBackend>, case: &Case) -> ImageData {
+    let mut scene = Scene::new(backend).expect("the backend creates the scene's pipelines");
+    scene.clock.set_date_str(DATE).expect("DATE is valid");
+    let day_texture = case.textured.then(|| scene.add_texture(&test_pattern()));
+    crate::populate(&mut scene, day_texture);
+    scene.restore_view(&case.view);
+    if case.wireframe {
+        scene.toggle_wireframe();
+    }
+
+    scene.update(0.0);
+    scene.render();
+    scene
+        .read_pixels()
+        .expect("golden-image backends read back their frames")
+}
+
+// Compares `image` with its reference, or replaces the reference when blessing.
+fn check(dir: &Path, case: &Case, image: &ImageData) -> Result<(), String> {
+    let reference_path = dir.join(format!("{}.png", case.name));
+    if env::var_os(BLESS_VAR).is_some() {
+        return fs::create_dir_all(dir)
+            .map_err(|err| err.to_string())
+            .and_then(|_| image.save(&reference_path).map_err(|err| err.to_string()))
+            .map_err(|err| format!("could not write {}: {}", reference_path.display(), err));
+    }
+
+    let reference = ImageData::load(&reference_path, true).map_err(|err| {
+        format!(
+            "no reference {} ({}), run with {}=1 to create it",
+            reference_path.display(),
+            err,
+            BLESS_VAR
+        )
+    })?;
+    if (reference.width, reference.height) != (image.width, image.height) {
+        return Err(format!(
+            "{}: rendered {}x{}, the reference is {}x{}",
+            case.name, image.width, image.height, reference.width, reference.height
+        ));
+    }
+
+    let comparison = compare(&reference, image, PIXEL_THRESHOLD);
+    let changed = comparison.differing as f32 / comparison.total as f32;
+    if changed <= MAX_CHANGED {
+        return Ok(());
+    }
+
+    let actual_path = dir.join(format!("{}.actual.png", case.name));
+    let diff_path = dir.join(format!("{}.diff.png", case.name));
+    for (path, image) in [(&actual_path, image), (&diff_path, &comparison.diff)] {
+        if let Err(err) = image.save(path) {
+            eprintln!("Could not write {}: {}", path.display(), err);
+        }
+    }
+    Err(format!(
+        "{}: {:.2}% of the pixels changed, see {} and {}",
+        case.name,
+        changed * 100.0,
+        actual_path.display(),
+        diff_path.display()
+    ))
+}
+
+fn run(backend: &str, create: impl Fn() -> Box<dyn RenderBackend>) {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(backend);
+    let failures: Vec<String> = cases()
+        .iter()
+        .filter_map(|case| check(&dir, case, &render(create(), case)).err())
+        .collect();
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+#[test]
+fn software_backend_matches_references() {
+    run("software", || Box::new(SoftwareBackend::new(WIDTH, HEIGHT)));
+}
+
+#[test]
+#[ignore = "needs EGL with an OpenGL 3.3 driver, e.g. Mesa's llvmpipe"]
+fn opengl_backend_matches_references() {
+    let _context = HeadlessContext::new(WIDTH, HEIGHT).unwrap_or_else(|err| panic!("{}", err));
+
+    // Neither the optional assets nor the user's post-processing chain are in the references,
+    // so both are looked up where nothing exists and the defaults apply.
+    let nowhere = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/golden/no-assets");
+    run("opengl", || {
+        let backend = GlBackend::new(&nowhere, &nowhere.join("post.cfg"));
+        Box::new(backend.unwrap_or_else(|err| panic!("{}", err)))
+    });
+}
@@ -3,75 +3,128 @@ extern crate glfw;
 
 use glfw::{Action, Context, Key, MouseButton, WindowEvent};
 
-use crate::core::constants::{HEIGHT, WIDTH};
+use crate::algebra::vec3::Vec3;
+use crate::core::constants::{DAY_TEXTURE, GLOBE_RADIUS, HEIGHT, WIDTH};
+use crate::objects::material::Material;
 use crate::objects::objects::Position;
 use crate::objects::sphere::{Sphere, SphereResolution};
+use crate::render::backend::opengl::GlBackend;
+use crate::render::backend::software::SoftwareBackend;
+use crate::render::backend::{BackendKind, Effect, RenderBackend, TextureId};
+use crate::render::post::config_path;
+use crate::scene::bookmarks::{default_path, Bookmarks, LAST_VIEW};
 use crate::scene::camera::CameraMovement;
 use crate::scene::core::Scene;
+use crate::scene::light::Light;
+
+use std::rc::Rc;
 
 mod algebra;
 mod core;
 mod objects;
+mod render;
 mod scene;
 mod shaders;
+mod textures;
+
+#[cfg(test)]
+mod golden;
 
 use std::fs::OpenOptions;
 use std::io::Write;
+use std::path::Path;
+
+const CAMERA_PATH_FILE: &str = "camera_path.txt";
+
+// (latitude, longitude) of the sites reachable with F1..F5.
+const SITES: [(f32, f32); 5] = [
+    (48.8566, 2.3522),
+    (35.6762, 139.6503),
+    (40.7128, -74.0060),
+    (-33.8688, 151.2093),
+    (-22.9068, -43.1729),
+];
+const SITE_KEYS: [Key; 5] = [Key::F1, Key::F2, Key::F3, Key::F4, Key::F5];
+const SLOT_KEYS: [Key; 9] = [
+    Key::Num1,
+    Key::Num2,
+    Key::Num3,
+    Key::Num4,
+    Key::Num5,
+    Key::Num6,
+    Key::Num7,
+    Key::Num8,
+    Key::Num9,
+];
 
 fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let backend_kind = BackendKind::from_args(&args).unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    });
+    if backend_kind == BackendKind::Software {
+        render_headless(&args);
+        return;
+    }
+
     use glfw::fail_on_errors;
     let mut glfw = glfw::init(fail_on_errors!()).unwrap();
 
-    glfw.window_hint(glfw::WindowHint::ContextVersion(3, 3));
-    glfw.window_hint(glfw::WindowHint::OpenGlForwardCompat(true));
+    match backend_kind {
+        BackendKind::OpenGl => {
+            glfw.window_hint(glfw::WindowHint::ContextVersion(3, 3));
+            glfw.window_hint(glfw::WindowHint::OpenGlForwardCompat(true));
+            glfw.window_hint(glfw::WindowHint::OpenGlProfile(
+                glfw::OpenGlProfileHint::Core,
+            ));
+        }
+        BackendKind::Software => unreachable!("rendered without a window"),
+    }
     glfw.window_hint(glfw::WindowHint::Resizable(true));
-    glfw.window_hint(glfw::WindowHint::OpenGlProfile(
-        glfw::OpenGlProfileHint::Core,
-    ));
 
     let (mut window, events) = glfw
         .create_window(WIDTH, HEIGHT, "OpenGL Sphere", glfw::WindowMode::Windowed)
         .expect("Failed to create GLFW window");
 
-    window.make_current();
     window.set_key_polling(true);
     window.set_cursor_pos_polling(true);
     window.set_mouse_button_polling(true);
+    window.set_framebuffer_size_polling(true);
 
-    gl::load_with(|symbol| window.get_proc_address(symbol) as *const _);
+    let backend: Result<Box<dyn RenderBackend>, _> = match backend_kind {
+        BackendKind::OpenGl => {
+            window.make_current();
+            gl::load_with(|symbol| window.get_proc_address(symbol) as *const _);
+            GlBackend::new(Path::new("."), &config_path())
+                .map(|backend| Box::new(backend) as Box<dyn RenderBackend>)
+                .map_err(|err| err.to_string())
+        }
+        BackendKind::Software => unreachable!("rendered without a window"),
+    };
+    let mut scene = backend
+        .map_err(|err| err.to_string())
+        .and_then(|backend| Scene::new(backend).map_err(|err| err.to_string()))
+        .unwrap_or_else(|err| {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        });
 
-    unsafe {
-        gl::Enable(gl::DEPTH_TEST);
-    }
+    set_date_from_args(&mut scene, &args);
+    let day_texture = scene.load_texture(DAY_TEXTURE, true);
+    populate(&mut scene, day_texture);
 
-    let mut scene = Scene::new();
-    scene.push_instance(Sphere::new(
-        1.0,
-        Position {
-            x: 0.0,
-            y: 0.0,
-            z: 0.0,
-        },
-        SphereResolution {
-            sectors: 64,
-            stacks: 64,
-        },
-    ));
+    // The framebuffer is larger than the window on high density displays.
+    let (width, height) = window.get_framebuffer_size();
+    scene.resize(width as u32, height as u32);
 
-    scene.push_instance(Sphere::new(
-        0.5,
-        Position {
-            x: 1.0,
-            y: 1.0,
-            z: 1.0,
-        },
-        SphereResolution {
-            sectors: 64,
-            stacks: 64,
-        },
-    ));
+    let mut bookmarks = Bookmarks::load_or_empty(default_path());
+    if let Some(view) = bookmarks.get(LAST_VIEW) {
+        scene.restore_view(view);
+    }
 
     let mut last_frame_time = glfw.get_time();
+    let mut last_title_time = last_frame_time;
     let mut last_x = window.get_size().0 as f32 / 2.0;
     let mut last_y = window.get_size().1 as f32 / 2.0;
     let mut first_mouse = true;
@@ -83,7 +136,20 @@ fn main() {
         last_frame_time = current_time;
 
         scene.update(delta_time);
-        scene.render();
+        let stats = scene.render();
+
+        if current_time - last_title_time >= 1.0 {
+            let shader_status = if scene.has_stale_shaders() {
+                " - shader error, see console"
+            } else {
+                ""
+            };
+            window.set_title(&format!(
+                "OpenGL Sphere - {} drawn, {} frustum culled, {} horizon culled{}",
+                stats.drawn, stats.frustum_culled, stats.horizon_culled, shader_status
+            ));
+            last_title_time = current_time;
+        }
 
         window.swap_buffers();
         glfw.poll_events();
@@ -91,6 +157,9 @@ fn main() {
         for (_, event) in glfw::flush_messages(&events) {
             match event {
                 WindowEvent::Key(Key::Escape, _, Action::Press, _) => window.set_should_close(true),
+                WindowEvent::FramebufferSize(width, height) => {
+                    scene.resize(width as u32, height as u32);
+                }
                 WindowEvent::Key(Key::Tab, _, Action::Press, _) => {
                     scene.toggle_wireframe();
                 }
@@ -118,6 +187,78 @@ fn main() {
                 WindowEvent::Key(Key::D, _, Action::Release, _) => {
                     scene.process_keyboard(CameraMovement::Right, false);
                 }
+                WindowEvent::Key(Key::E, _, Action::Press, _) => {
+                    scene.cycle_flight_easing();
+                }
+                WindowEvent::Key(Key::T, _, Action::Press, _) => {
+                    scene.cycle_tone_mapper();
+                }
+                WindowEvent::Key(Key::X, _, Action::Press, _) => {
+                    scene.toggle_effect(Effect::AutoExposure);
+                }
+                WindowEvent::Key(Key::RightBracket, _, Action::Press, _) => {
+                    scene.adjust_exposure(0.5);
+                }
+                WindowEvent::Key(Key::LeftBracket, _, Action::Press, _) => {
+                    scene.adjust_exposure(-0.5);
+                }
+                WindowEvent::Key(Key::H, _, Action::Press, _) => {
+                    scene.toggle_effect(Effect::Shadows);
+                }
+                WindowEvent::Key(Key::V, _, Action::Press, _) => {
+                    scene.toggle_effect(Effect::PostProcessing);
+                }
+                WindowEvent::Key(Key::C, _, Action::Press, _) => {
+                    scene.toggle_effect(Effect::Clouds);
+                }
+                WindowEvent::Key(Key::Space, _, Action::Press, _) => {
+                    scene.clock.toggle_pause();
+                }
+                WindowEvent::Key(Key::Equal, _, Action::Press, _) => {
+                    scene.clock.speed_up();
+                }
+                WindowEvent::Key(Key::Minus, _, Action::Press, _) => {
+                    scene.clock.slow_down();
+                }
+                WindowEvent::Key(Key::R, _, Action::Press, _) => {
+                    if scene.is_recording() {
+                        if let Err(err) = scene.stop_recording(Path::new(CAMERA_PATH_FILE)) {
+                            eprintln!("Could not save camera path: {}", err);
+                        }
+                    } else {
+                        scene.start_recording();
+                    }
+                }
+                WindowEvent::Key(Key::P, _, Action::Press, _) => {
+                    if scene.is_playing() {
+                        scene.stop_playback();
+                    } else if let Err(err) = scene.start_playback(Path::new(CAMERA_PATH_FILE)) {
+                        eprintln!("Could not play camera path: {}", err);
+                    }
+                }
+                WindowEvent::Key(Key::O, _, Action::Press, _) => {
+                    scene.toggle_camera_mode();
+                }
+                WindowEvent::Key(key, _, Action::Press, modifiers) if SLOT_KEYS.contains(&key) => {
+                    let slot = (SLOT_KEYS.iter().position(|k| *k == key).unwrap() + 1).to_string();
+                    if modifiers.contains(glfw::Modifiers::Control) {
+                        bookmarks.set(&slot, scene.view_state());
+                        if let Err(err) = bookmarks.save() {
+                            eprintln!(
+                                "Could not save camera views to {}: {}",
+                                bookmarks.path().display(),
+                                err
+                            );
+                        }
+                    } else if let Some(view) = bookmarks.get(&slot) {
+                        scene.recall_view(view);
+                    }
+                }
+                WindowEvent::Key(key, _, Action::Press, _) if SITE_KEYS.contains(&key) => {
+                    let site = SITE_KEYS.iter().position(|k| *k == key).unwrap();
+                    let (lat, lon) = SITES[site];
+                    scene.fly_to(lat, lon, 0.3, 0.0, -60.0, 4.0);
+                }
                 WindowEvent::MouseButton(MouseButton::Button2, Action::Press, _) => {
                     right_mouse_pressed = true;
                     first_mouse = true;
@@ -149,4 +290,113 @@ fn main() {
             }
         }
     }
+
+    bookmarks.set(LAST_VIEW, scene.view_state());
+    if let Err(err) = bookmarks.save() {
+        eprintln!(
+            "Could not save camera views to {}: {}",
+            bookmarks.path().display(),
+            err
+        );
+    }
+}
+
+// The two spheres and the extra lights every view of the scene shows.
+fn populate(scene: &mut Scene, day_texture: Option<TextureId>) {
+    let globe = scene.push_instance(
+        Sphere::new(
+            GLOBE_RADIUS,
+            Position {
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+            },
+            SphereResolution {
+                sectors: 64,
+                stacks: 64,
+            },
+        )
+        .with_material(Rc::new(Material::globe(day_texture))),
+    );
+    if let Err(err) = globe {
+        eprintln!("{}", err);
+    }
+
+    let sphere = scene.push_instance(
+        Sphere::new(
+            0.5,
+            Position {
+                x: 1.0,
+                y: 1.0,
+                z: 1.0,
+            },
+            SphereResolution {
+                sectors: 64,
+                stacks: 64,
+            },
+        )
+        .with_material(Rc::new(Material {
+            roughness: 0.3,
+            metalness: 1.0,
+            ..Material::standard(Vec3::new(0.95, 0.64, 0.54))
+        })),
+    );
+    if let Err(err) = sphere {
+        eprintln!("{}", err);
+    }
+
+    scene.push_light(Light::point(
+        Vec3::new(1.6, 1.6, 1.6),
+        Vec3::new(1.0, 0.6, 0.3),
+        1.0,
+        Vec3::new(1.0, 0.7, 1.8),
+    ));
+    scene.push_light(Light::spot(
+        Vec3::new(1.0, 1.0, 2.0),
+        Vec3::new(0.0, 0.0, -1.0),
+        Vec3::new(0.3, 0.5, 1.0),
+        2.0,
+    ));
+}
+
+fn set_date_from_args(scene: &mut Scene, args: &[String]) {
+    if let Some(index) = args.iter().position(|arg| arg == "--date") {
+        match args.get(index + 1) {
+            Some(date) => {
+                if let Err(err) = scene.clock.set_date_str(date) {
+                    eprintln!("{}", err);
+                }
+            }
+            None => eprintln!("--date expects a value such as 2024-06-21T12:00:00"),
+        }
+    }
+}
+
+// Renders one frame with the software backend and writes it to `--output`, frame.png by
+// default.
+fn render_headless(args: &[String]) {
+    let output = args
+        .iter()
+        .position(|arg| arg == "--output")
+        .and_then(|index| args.get(index + 1))
+        .map_or("frame.png", String::as_str);
+
+    let mut scene =
+        Scene::new(Box::new(SoftwareBackend::new(WIDTH, HEIGHT))).unwrap_or_else(|err| {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        });
+    set_date_from_args(&mut scene, args);
+    let day_texture = scene.load_texture(DAY_TEXTURE, true);
+    populate(&mut scene, day_texture);
+
+    scene.update(0.0);
+    scene.render();
+    let frame = scene
+        .read_pixels()
+        .expect("the software backend keeps its frame");
+    if let Err(err) = frame.save(Path::new(output)) {
+        eprintln!("Could not write {}: {}", output, err);
+        std::process::exit(1);
+    }
 }
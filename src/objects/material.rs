@@ -0,0 +1,80 @@
+use crate::algebra::vec3::Vec3;
+use crate::render::backend::TextureId;
+use crate::shaders::program::ShaderProgram;
+use crate::textures::texture::Texture;
+
+// Texture unit for the base colour map, after the globe's night, water and cloud maps.
+pub const BASE_COLOR_UNIT: u32 = 4;
+
+// Which program a material is drawn with.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Shading {
+    // The Earth surface: water glint, night lights and cloud shadows.
+    Globe,
+    // Generic lit surface for everything else.
+    Standard,
+}
+
+pub struct Material {
+    pub shading: Shading,
+    pub base_color: Vec3,
+    pub base_color_map: Option<TextureId>,
+    pub roughness: f32,
+    pub metalness: f32,
+    pub emissive: Vec3,
+    // 1.0 is opaque, anything lower is blended after the opaque draws.
+    pub opacity: f32,
+}
+
+impl Material {
+    pub fn standard(base_color: Vec3) -> Self {
+        Material {
+            shading: Shading::Standard,
+            base_color,
+            base_color_map: None,
+            roughness: 0.5,
+            metalness: 0.0,
+            emissive: Vec3::new(0.0, 0.0, 0.0),
+            opacity: 1.0,
+        }
+    }
+
+    pub fn globe(base_color_map: Option<TextureId>) -> Self {
+        Material {
+            shading: Shading::Globe,
+            base_color: if base_color_map.is_some() {
+                Vec3::new(1.0, 1.0, 1.0)
+            } else {
+                Vec3::new(0.25, 0.45, 0.3)
+            },
+            base_color_map,
+            roughness: 0.9,
+            ..Material::standard(Vec3::new(1.0, 1.0, 1.0))
+        }
+    }
+
+    pub fn is_transparent(&self) -> bool {
+        self.opacity < 1.0
+    }
+
+    // `base_color_map` is the GL texture behind `self.base_color_map`.
+    pub fn apply(&self, shader: &ShaderProgram, base_color_map: Option<&Texture>) {
+        shader.set_vec3("material.baseColor", &self.base_color);
+        shader.set_float("material.roughness", self.roughness);
+        shader.set_float("material.metalness", self.metalness);
+        shader.set_vec3("material.emissive", &self.emissive);
+        shader.set_float("material.opacity", self.opacity);
+
+        shader.set_bool("material.hasBaseColorMap", base_color_map.is_some());
+        if let Some(texture) = base_color_map {
+            texture.bind(BASE_COLOR_UNIT);
+            shader.set_sampler("material.baseColorMap", BASE_COLOR_UNIT);
+        }
+    }
+}
+
+impl Default for Material {
+    fn default() -> Self {
+        Material::standard(Vec3::new(0.8, 0.8, 0.8))
+    }
+}
@@ -1,2 +1,3 @@
+pub mod material;
 pub mod objects;
 pub mod sphere;
@@ -1,5 +1,16 @@
+use crate::algebra::vec3::Vec3;
+use crate::objects::material::Material;
+use crate::render::backend::MeshData;
+
+use std::rc::Rc;
+
 pub trait Object {
-    fn draw(&self);
+    // Uploaded once by whichever backend draws the object.
+    fn mesh(&self) -> &MeshData;
+    fn bounding_sphere(&self) -> BoundingSphere;
+    fn material(&self) -> &Rc<Material>;
+    fn casts_shadows(&self) -> bool;
+    fn receives_shadows(&self) -> bool;
 }
 
 pub struct Position {
@@ -7,3 +18,9 @@ pub struct Position {
     pub y: f32,
     pub z: f32,
 }
+
+#[derive(Clone, Copy)]
+pub struct BoundingSphere {
+    pub center: Vec3,
+    pub radius: f32,
+}
@@ -1,12 +1,18 @@
 use crate::algebra::vec3::Vec3;
-use crate::objects::objects::{Object, Position};
+use crate::objects::material::Material;
+use crate::objects::objects::{BoundingSphere, Object, Position};
+use crate::render::backend::MeshData;
+use crate::render::vertex::{Semantic, VertexLayout};
 
 use std::f32::consts::PI;
-use std::ptr;
+use std::rc::Rc;
 
 pub struct Sphere {
-    pub vao: gl::types::GLuint,
-    pub index_count: usize,
+    pub mesh: MeshData,
+    pub bounds: BoundingSphere,
+    pub material: Rc<Material>,
+    pub casts_shadows: bool,
+    pub receives_shadows: bool,
 }
 
 pub struct SphereResolution {
@@ -16,72 +22,58 @@ pub struct SphereResolution {
 
 impl Sphere {
     pub fn new(radius: f32, position: Position, res: SphereResolution) -> Self {
+        let bounds = BoundingSphere {
+            center: Vec3::new(position.x, position.y, position.z),
+            radius,
+        };
         let (vertices, indices) =
             generate_sphere_vertices(radius, position, res.sectors, res.stacks);
-        let index_count = indices.len();
-
-        let (vao, _vbo, _ebo) = unsafe {
-            let (mut vbo, mut vao, mut ebo) = (0, 0, 0);
-            gl::GenVertexArrays(1, &mut vao);
-            gl::GenBuffers(1, &mut vbo);
-            gl::GenBuffers(1, &mut ebo);
-
-            gl::BindVertexArray(vao);
-
-            gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
-            gl::BufferData(
-                gl::ARRAY_BUFFER,
-                (vertices.len() * size_of::<gl::types::GLfloat>()) as gl::types::GLsizeiptr,
-                vertices.as_ptr() as *const _,
-                gl::STATIC_DRAW,
-            );
-
-            gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, ebo);
-            gl::BufferData(
-                gl::ELEMENT_ARRAY_BUFFER,
-                (indices.len() * size_of::<gl::types::GLuint>()) as gl::types::GLsizeiptr,
-                indices.as_ptr() as *const _,
-                gl::STATIC_DRAW,
-            );
-
-            gl::VertexAttribPointer(
-                0,
-                3,
-                gl::FLOAT,
-                gl::FALSE,
-                6 * size_of::<gl::types::GLfloat>() as gl::types::GLsizei,
-                ptr::null(),
-            );
-            gl::EnableVertexAttribArray(0);
-
-            gl::VertexAttribPointer(
-                1,
-                3,
-                gl::FLOAT,
-                gl::FALSE,
-                6 * size_of::<gl::types::GLfloat>() as gl::types::GLsizei,
-                (3 * size_of::<gl::types::GLfloat>()) as *const _,
-            );
-            gl::EnableVertexAttribArray(1);
-
-            (vao, vbo, ebo)
-        };
 
-        Sphere { vao, index_count }
+        Sphere {
+            mesh: MeshData {
+                vertices,
+                indices,
+                layout: Sphere::layout(),
+            },
+            bounds,
+            material: Rc::new(Material::default()),
+            casts_shadows: true,
+            receives_shadows: true,
+        }
+    }
+
+    // Matches the vertices from `generate_sphere_vertices`.
+    pub fn layout() -> VertexLayout {
+        VertexLayout::new()
+            .with(Semantic::Position, 3)
+            .with(Semantic::Normal, 3)
+    }
+
+    pub fn with_material(mut self, material: Rc<Material>) -> Self {
+        self.material = material;
+        self
     }
 }
 
 impl Object for Sphere {
-    fn draw(&self) {
-        unsafe {
-            gl::BindVertexArray(self.vao);
-            gl::DrawElements(
-                gl::TRIANGLES,
-                self.index_count as i32,
-                gl::UNSIGNED_INT,
-                ptr::null(),
-            );
-        }
+    fn mesh(&self) -> &MeshData {
+        &self.mesh
+    }
+
+    fn bounding_sphere(&self) -> BoundingSphere {
+        self.bounds
+    }
+
+    fn material(&self) -> &Rc<Material> {
+        &self.material
+    }
+
+    fn casts_shadows(&self) -> bool {
+        self.casts_shadows
+    }
+
+    fn receives_shadows(&self) -> bool {
+        self.receives_shadows
     }
 }
 
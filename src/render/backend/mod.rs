@@ -0,0 +1,160 @@
+use crate::objects::material::{Material, Shading};
+use crate::render::vertex::VertexLayout;
+use crate::scene::camera::Camera;
+use crate::scene::light::Light;
+use crate::shaders::error::ShaderError;
+use crate::textures::image::ImageData;
+
+use std::error::Error;
+use std::fmt;
+
+pub mod opengl;
+pub mod software;
+
+// Handles into a backend's own tables, in creation order.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct MeshId(usize);
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct TextureId(usize);
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct PipelineId(usize);
+
+// Indexed triangles, vertices interleaved as `layout` describes.
+pub struct MeshData {
+    pub vertices: Vec<f32>,
+    pub indices: Vec<u32>,
+    pub layout: VertexLayout,
+}
+
+// How surfaces drawn with a pipeline are shaded and combined with what is already there.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct PipelineDesc {
+    pub shading: Shading,
+    // Alpha blended without depth writes, for materials that are not opaque.
+    pub blended: bool,
+}
+
+// Shadow casters are drawn into the shadow maps, then everything visible into the scene.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Pass {
+    Shadow,
+    Scene,
+}
+
+// What every pass of a frame shares.
+pub struct Frame<'a> {
+    pub camera: &'a Camera,
+    // The sun first.
+    pub lights: &'a [Light],
+    pub wireframe: bool,
+    pub unix_seconds: f64,
+    // Seconds since start, for animated surfaces.
+    pub elapsed: f32,
+}
+
+pub struct DrawCall<'a> {
+    pub pipeline: PipelineId,
+    pub mesh: MeshId,
+    pub material: &'a Material,
+    pub receives_shadows: bool,
+}
+
+// Optional parts of the image a backend may or may not render, toggled at runtime.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Effect {
+    Shadows,
+    Clouds,
+    PostProcessing,
+    AutoExposure,
+}
+
+// What the scene needs from a graphics API: meshes, textures and pipelines created up
+// front, then per frame a shadow pass and a scene pass of draws.
+pub trait RenderBackend {
+    fn create_mesh(&mut self, mesh: &MeshData) -> Result<MeshId, BackendError>;
+    fn create_texture(&mut self, image: &ImageData) -> TextureId;
+    fn create_pipeline(&mut self, desc: &PipelineDesc) -> Result<PipelineId, BackendError>;
+
+    // Advances whatever the backend animates or adapts on its own, once per frame.
+    fn update(&mut self, _frame: &Frame, _delta_time: f32, _simulated_seconds: f64) {}
+
+    // False when the backend has no such pass, the scene then skips its draws.
+    fn begin_pass(&mut self, pass: Pass, frame: &Frame) -> bool;
+    fn draw(&mut self, draw: &DrawCall);
+    // Ending the scene pass finishes the frame.
+    fn end_pass(&mut self, frame: &Frame);
+
+    // The window's framebuffer changed size, in pixels. False when the backend keeps drawing
+    // at its own fixed size.
+    fn resize(&mut self, _width: u32, _height: u32) -> bool {
+        false
+    }
+
+    fn toggle_effect(&mut self, _effect: Effect) {}
+    fn cycle_tone_mapper(&mut self) {}
+    // Exposure compensation, in stops.
+    fn adjust_exposure(&mut self, _stops: f32) {}
+    // Set while an edited shader fails to build and its previous version stays in use.
+    fn has_stale_shaders(&self) -> bool {
+        false
+    }
+
+    // The last finished frame as sRGB pixels, when the backend can read it back.
+    fn read_pixels(&mut self) -> Option<ImageData> {
+        None
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BackendKind {
+    OpenGl,
+    // The CPU rasterizer, rendering a single frame without a window.
+    Software,
+}
+
+impl BackendKind {
+    // `--backend gl` or `software`, OpenGL when absent.
+    pub fn from_args(args: &[String]) -> Result<Self, String> {
+        let Some(index) = args.iter().position(|arg| arg == "--backend") else {
+            return Ok(BackendKind::OpenGl);
+        };
+        match args.get(index + 1).map(String::as_str) {
+            Some("gl") => Ok(BackendKind::OpenGl),
+            Some("software") => Ok(BackendKind::Software),
+            _ => Err("--backend expects gl or software".to_string()),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum BackendError {
+    Shader(ShaderError),
+    // A mesh the backend's pipelines cannot draw.
+    Mesh(String),
+}
+
+impl fmt::Display for BackendError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BackendError::Shader(err) => write!(f, "{}", err),
+            BackendError::Mesh(message) => write!(f, "unsupported mesh: {}", message),
+        }
+    }
+}
+
+impl Error for BackendError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            BackendError::Shader(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<ShaderError> for BackendError {
+    fn from(err: ShaderError) -> Self {
+        BackendError::Shader(err)
+    }
+}
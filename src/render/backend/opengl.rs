@@ -0,0 +1,443 @@
+use crate::algebra::mat4::Mat4;
+use crate::core::constants::{
+    CLOUDS_TEXTURE, HEIGHT, NIGHT_LIGHTS_TEXTURE, STAR_CATALOGUES, WATER_MASK_TEXTURE,
+    WATER_NORMALS_TEXTURE, WIDTH,
+};
+use crate::objects::material::{Material, Shading};
+use crate::render::backend::{
+    BackendError, DrawCall, Effect, Frame, MeshData, MeshId, Pass, PipelineDesc, PipelineId,
+    RenderBackend, TextureId,
+};
+use crate::render::buffer::Mesh;
+use crate::render::framebuffer::bind_default;
+use crate::render::post::{PostConfig, PostProcessing};
+use crate::scene::atmosphere::Atmosphere;
+use crate::scene::camera::{CameraBuffer, CAMERA_BINDING};
+use crate::scene::clouds::CloudLayer;
+use crate::scene::depth::{clip_planes, DepthMode};
+use crate::scene::hdr::{HdrTarget, ToneMapping};
+use crate::scene::light::{LightBuffer, LIGHTS_BINDING, MAX_LIGHTS};
+use crate::scene::shadows::{ShadowCascades, CASCADES};
+use crate::scene::stars::{load_catalogue, Starfield};
+use crate::shaders::cache::{ProgramCache, ProgramId, VariantKey};
+use crate::shaders::error::ShaderError;
+use crate::shaders::program::ShaderProgram;
+use crate::textures::image::ImageData;
+use crate::textures::texture::Texture;
+
+use std::path::Path;
+
+// After the globe's night, water, cloud and base colour maps.
+const SHADOW_MAP_UNIT: u32 = 5;
+// Seconds between checks of the shader sources for changes.
+const SHADER_POLL_INTERVAL: f32 = 0.5;
+
+// The OpenGL 3.3 renderer: HDR scene with cascaded shadows, stars, clouds and atmosphere,
+// then bloom, tone mapping and the display passes. Needs a current context.
+pub struct GlBackend {
+    programs: ProgramCache,
+    pipelines: Vec<(PipelineDesc, ProgramId)>,
+    meshes: Vec<Mesh>,
+    textures: Vec<Texture>,
+    camera_buffer: CameraBuffer,
+    light_buffer: LightBuffer,
+    night_lights: Option<Texture>,
+    water_mask: Option<Texture>,
+    water_normals: Option<Texture>,
+    starfield: Option<Starfield>,
+    clouds: Option<CloudLayer>,
+    atmosphere: Atmosphere,
+    depth_mode: DepthMode,
+    // The window's framebuffer size, which the HDR and post-processing targets match.
+    width: u32,
+    height: u32,
+    hdr_target: HdrTarget,
+    tone_mapping: ToneMapping,
+    post_processing: PostProcessing,
+    shadows: ShadowCascades,
+    shader_poll: f32,
+    stale_shaders: bool,
+    pass: Option<Pass>,
+    // Recorded during the shadow pass, drawn once per cascade when it ends.
+    casters: Vec<MeshId>,
+    // Program and material state left by the previous draw of the scene pass.
+    current_pipeline: Option<PipelineId>,
+    current_material: Option<*const Material>,
+}
+
+impl GlBackend {
+    // The optional assets are looked up under `asset_root`, e.g. the working directory, and
+    // the post-processing chain is read from `post_config`, see render::post::config_path.
+    pub fn new(asset_root: &Path, post_config: &Path) -> Result<Self, ShaderError> {
+        unsafe {
+            gl::Enable(gl::DEPTH_TEST);
+        }
+
+        let night_lights = Texture::load_optional_srgb(&asset_root.join(NIGHT_LIGHTS_TEXTURE));
+        let water_mask = Texture::load_optional(&asset_root.join(WATER_MASK_TEXTURE));
+        let water_normals = Texture::load_optional(&asset_root.join(WATER_NORMALS_TEXTURE));
+        if let Some(texture) = &water_normals {
+            texture.set_repeat();
+        }
+
+        let stars = STAR_CATALOGUES
+            .iter()
+            .map(|name| asset_root.join(name))
+            .find(|path| path.exists())
+            .and_then(|path| match load_catalogue(&path) {
+                Ok(stars) => Some(stars),
+                Err(err) => {
+                    eprintln!("Could not load {}: {}", path.display(), err);
+                    None
+                }
+            });
+        let starfield = stars.map(|stars| Starfield::new(&stars)).transpose()?;
+
+        let depth_mode = DepthMode::detect();
+        depth_mode.apply();
+        let post_config = PostConfig::load(post_config).unwrap_or_else(|err| {
+            eprintln!("Could not load {}: {}", post_config.display(), err);
+            PostConfig::default()
+        });
+
+        Ok(GlBackend {
+            programs: ProgramCache::new(),
+            pipelines: Vec::new(),
+            meshes: Vec::new(),
+            textures: Vec::new(),
+            camera_buffer: CameraBuffer::new(),
+            light_buffer: LightBuffer::new(),
+            night_lights,
+            water_mask,
+            water_normals,
+            starfield,
+            clouds: CloudLayer::load(&asset_root.join(CLOUDS_TEXTURE))?,
+            atmosphere: Atmosphere::earth()?,
+            depth_mode,
+            width: WIDTH,
+            height: HEIGHT,
+            hdr_target: HdrTarget::new(WIDTH, HEIGHT),
+            tone_mapping: ToneMapping::new()?,
+            post_processing: PostProcessing::new(&post_config, WIDTH, HEIGHT)?,
+            shadows: ShadowCascades::new()?,
+            shader_poll: 0.0,
+            stale_shaders: false,
+            pass: None,
+            casters: Vec::new(),
+            current_pipeline: None,
+            current_material: None,
+        })
+    }
+
+    fn aspect(&self) -> f32 {
+        self.width as f32 / self.height as f32
+    }
+
+    fn globe_program(&self) -> Option<&ShaderProgram> {
+        self.pipelines
+            .iter()
+            .find(|(desc, _)| desc.shading == Shading::Globe)
+            .map(|(_, program)| self.programs.get(*program))
+    }
+
+    fn shader_programs(&mut self) -> Vec<&mut ShaderProgram> {
+        let mut programs = self.programs.shader_programs();
+        if let Some(starfield) = self.starfield.as_mut() {
+            programs.extend(starfield.shader_programs());
+        }
+        if let Some(clouds) = self.clouds.as_mut() {
+            programs.extend(clouds.shader_programs());
+        }
+        programs.extend(self.atmosphere.shader_programs());
+        programs.extend(self.tone_mapping.shader_programs());
+        programs.extend(self.post_processing.shader_programs());
+        programs.extend(self.shadows.shader_programs());
+        programs
+    }
+
+    // Swaps in programs whose sources changed, keeping the old ones when they fail to build.
+    fn reload_shaders(&mut self) {
+        let mut stale = false;
+        for program in self.shader_programs() {
+            if let Err(err) = program.reload_if_changed() {
+                eprintln!("{}", err);
+            }
+            stale |= program.stale;
+        }
+        self.stale_shaders = stale;
+    }
+
+    fn begin_scene_pass(&mut self, frame: &Frame) {
+        self.hdr_target.bind();
+        unsafe {
+            gl::PolygonMode(
+                gl::FRONT_AND_BACK,
+                if frame.wireframe { gl::LINE } else { gl::FILL },
+            );
+            gl::ClearColor(0.0, 0.0, 0.0, 1.0);
+            gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+        }
+
+        let camera = frame.camera;
+        let view = camera.get_view_matrix();
+        let fov_y = camera.fov.to_radians();
+        let aspect = self.aspect();
+        let (near, far) = clip_planes(camera.position);
+        let projection = self.depth_mode.projection(fov_y, aspect, near, far);
+        self.camera_buffer
+            .upload(&view, &projection, &camera.position, self.depth_mode, far);
+
+        if let Some(starfield) = &self.starfield {
+            starfield.render(frame.unix_seconds);
+        }
+
+        self.light_buffer.upload(frame.lights.iter());
+
+        for (_, program) in &self.pipelines {
+            let program = self.programs.get(*program);
+            program.use_program();
+            program.set_mat4("model", &Mat4::identity());
+            self.shadows.update_shader(program, SHADOW_MAP_UNIT);
+        }
+
+        if let Some(globe_program) = self.globe_program() {
+            globe_program.use_program();
+
+            globe_program.set_bool("hasNightLights", self.night_lights.is_some());
+            if let Some(texture) = &self.night_lights {
+                texture.bind(0);
+                globe_program.set_sampler("nightLights", 0);
+            }
+
+            globe_program.set_bool("hasWaterMask", self.water_mask.is_some());
+            if let Some(texture) = &self.water_mask {
+                texture.bind(1);
+                globe_program.set_sampler("waterMask", 1);
+            }
+
+            globe_program.set_bool("hasWaterNormals", self.water_normals.is_some());
+            if let Some(texture) = &self.water_normals {
+                texture.bind(2);
+                globe_program.set_sampler("waterNormals", 2);
+            }
+            globe_program.set_float("time", frame.elapsed);
+
+            match &self.clouds {
+                Some(clouds) => clouds.update_shader(globe_program, 3),
+                None => globe_program.set_bool("hasClouds", false),
+            }
+        }
+
+        self.current_pipeline = None;
+        self.current_material = None;
+    }
+
+    fn end_scene_pass(&mut self, frame: &Frame) {
+        unsafe {
+            gl::DepthMask(gl::TRUE);
+            gl::Disable(gl::BLEND);
+        }
+
+        let camera = frame.camera;
+        let sun = &frame.lights[0];
+        if let Some(clouds) = &self.clouds {
+            clouds.render(sun, camera.position.length());
+        }
+
+        let (near, far) = clip_planes(camera.position);
+        self.atmosphere
+            .render(camera, sun, &self.hdr_target, self.depth_mode, near, far);
+        self.tone_mapping.measure(&self.hdr_target);
+        self.post_processing
+            .render(&self.hdr_target, &self.tone_mapping);
+    }
+
+    // Switches program, blending and material uniforms only when they differ from the
+    // previous draw.
+    fn draw_surface(&mut self, draw: &DrawCall) {
+        let (desc, program) = self.pipelines[draw.pipeline.0];
+        let program = self.programs.get(program);
+        if self.current_pipeline != Some(draw.pipeline) {
+            program.use_program();
+            unsafe {
+                if desc.blended {
+                    gl::Enable(gl::BLEND);
+                    gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
+                    gl::DepthMask(gl::FALSE);
+                } else {
+                    gl::Disable(gl::BLEND);
+                    gl::DepthMask(gl::TRUE);
+                }
+            }
+            self.current_pipeline = Some(draw.pipeline);
+            self.current_material = None;
+        }
+
+        let material: *const Material = draw.material;
+        if self.current_material != Some(material) {
+            let base_color_map = draw.material.base_color_map.map(|id| &self.textures[id.0]);
+            draw.material.apply(program, base_color_map);
+            self.current_material = Some(material);
+        }
+        program.set_bool("receivesShadows", draw.receives_shadows);
+        self.meshes[draw.mesh.0].draw();
+    }
+}
+
+impl RenderBackend for GlBackend {
+    // Every surface program reads the same inputs, and so does the shadow program.
+    fn create_mesh(&mut self, mesh: &MeshData) -> Result<MeshId, BackendError> {
+        for (_, program) in &self.pipelines {
+            mesh.layout.validate(self.programs.get(*program))?;
+        }
+        mesh.layout.validate(self.shadows.shader_program())?;
+        self.meshes.push(Mesh::new(mesh));
+        Ok(MeshId(self.meshes.len() - 1))
+    }
+
+    fn create_texture(&mut self, image: &ImageData) -> TextureId {
+        self.textures.push(Texture::from_image(image));
+        TextureId(self.textures.len() - 1)
+    }
+
+    fn create_pipeline(&mut self, desc: &PipelineDesc) -> Result<PipelineId, BackendError> {
+        let program = self.programs.get_or_build(&surface_variant(desc.shading))?;
+        for program in self.programs.shader_programs() {
+            program.bind_uniform_block("Lights", LIGHTS_BINDING);
+            program.bind_uniform_block("Camera", CAMERA_BINDING);
+        }
+        self.pipelines.push((*desc, program));
+        Ok(PipelineId(self.pipelines.len() - 1))
+    }
+
+    fn update(&mut self, frame: &Frame, delta_time: f32, simulated_seconds: f64) {
+        self.shader_poll += delta_time;
+        if self.shader_poll >= SHADER_POLL_INTERVAL {
+            self.shader_poll = 0.0;
+            self.reload_shaders();
+        }
+
+        if let Some(clouds) = self.clouds.as_mut() {
+            clouds.update(simulated_seconds);
+        }
+        self.tone_mapping.update(delta_time);
+
+        let (near, _) = clip_planes(frame.camera.position);
+        self.shadows.update(
+            frame.camera,
+            frame.lights[0].direction,
+            near,
+            self.aspect(),
+            self.depth_mode,
+        );
+    }
+
+    fn begin_pass(&mut self, pass: Pass, frame: &Frame) -> bool {
+        let enabled = match pass {
+            Pass::Shadow => {
+                self.casters.clear();
+                self.shadows.enabled
+            }
+            Pass::Scene => {
+                self.begin_scene_pass(frame);
+                true
+            }
+        };
+        if enabled {
+            self.pass = Some(pass);
+        }
+        enabled
+    }
+
+    fn draw(&mut self, draw: &DrawCall) {
+        match self.pass {
+            Some(Pass::Shadow) => self.casters.push(draw.mesh),
+            Some(Pass::Scene) => self.draw_surface(draw),
+            None => {}
+        }
+    }
+
+    fn end_pass(&mut self, frame: &Frame) {
+        match self.pass.take() {
+            Some(Pass::Shadow) => {
+                let casters: Vec<&Mesh> =
+                    self.casters.iter().map(|id| &self.meshes[id.0]).collect();
+                self.shadows.render(&casters, self.depth_mode);
+            }
+            Some(Pass::Scene) => self.end_scene_pass(frame),
+            None => {}
+        }
+    }
+
+    fn resize(&mut self, width: u32, height: u32) -> bool {
+        self.width = width;
+        self.height = height;
+        self.hdr_target = HdrTarget::new(width, height);
+        self.post_processing.resize(width, height);
+        true
+    }
+
+    fn toggle_effect(&mut self, effect: Effect) {
+        match effect {
+            Effect::Shadows => self.shadows.toggle(),
+            Effect::Clouds => {
+                if let Some(clouds) = self.clouds.as_mut() {
+                    clouds.toggle();
+                }
+            }
+            Effect::PostProcessing => self.post_processing.toggle(),
+            Effect::AutoExposure => self.tone_mapping.exposure.toggle_auto(),
+        }
+    }
+
+    fn cycle_tone_mapper(&mut self) {
+        self.tone_mapping.tone_mapper = self.tone_mapping.tone_mapper.next();
+    }
+
+    fn adjust_exposure(&mut self, stops: f32) {
+        self.tone_mapping.exposure.compensation += stops;
+    }
+
+    fn has_stale_shaders(&self) -> bool {
+        self.stale_shaders
+    }
+
+    // Reads the window's back buffer, so before the frame is swapped.
+    fn read_pixels(&mut self) -> Option<ImageData> {
+        let row = self.width as usize * 4;
+        let mut pixels = vec![0u8; row * self.height as usize];
+        bind_default();
+        unsafe {
+            gl::PixelStorei(gl::PACK_ALIGNMENT, 1);
+            gl::ReadPixels(
+                0,
+                0,
+                self.width as i32,
+                self.height as i32,
+                gl::RGBA,
+                gl::UNSIGNED_BYTE,
+                pixels.as_mut_ptr() as *mut _,
+            );
+        }
+
+        // GL rows run bottom to top.
+        Some(ImageData {
+            width: self.width,
+            height: self.height,
+            pixels: pixels.chunks_exact(row).rev().flatten().copied().collect(),
+            srgb: true,
+        })
+    }
+}
+
+// Both surface programs share one source, the globe variant adds water, night lights and
+// cloud shadows.
+fn surface_variant(shading: Shading) -> VariantKey {
+    let key = VariantKey::new("shader.vert", "surface.frag")
+        .define("MAX_LIGHTS", MAX_LIGHTS)
+        .define("CASCADES", CASCADES);
+    match shading {
+        Shading::Globe => key.define("GLOBE", 1),
+        Shading::Standard => key,
+    }
+}
@@ -0,0 +1,580 @@
+use crate::algebra::mat4::Mat4;
+use crate::algebra::vec3::Vec3;
+use crate::render::backend::{
+    BackendError, DrawCall, Effect, Frame, MeshData, MeshId, Pass, PipelineDesc, PipelineId,
+    RenderBackend, TextureId,
+};
+use crate::render::vertex::Semantic;
+use crate::scene::depth::{clip_planes, reversed_infinite_perspective};
+use crate::scene::hdr::{Exposure, ToneMapper};
+use crate::scene::light::{Light, LightKind, MAX_LIGHTS};
+use crate::textures::image::ImageData;
+
+use std::f32::consts::PI;
+
+// Half the width of a wireframe line, in pixels.
+const LINE_HALF_WIDTH: f32 = 0.5;
+// Darker pixels are left out of the metered exposure, as MIN_LUMINANCE in luminance.frag.
+const MIN_METERED_LUMINANCE: f32 = 1e-4;
+
+struct SoftwareMesh {
+    positions: Vec<Vec3>,
+    normals: Vec<Vec3>,
+    indices: Vec<u32>,
+}
+
+// Decoded to linear values once, sampled bilinearly without mipmaps.
+struct SoftwareTexture {
+    width: usize,
+    height: usize,
+    texels: Vec<Vec3>,
+}
+
+// What a vertex hands to the rasterizer: clip space position and the world space varyings.
+#[derive(Clone, Copy)]
+struct ClipVertex {
+    clip: [f32; 4],
+    world: Vec3,
+    normal: Vec3,
+}
+
+struct ScreenVertex {
+    x: f32,
+    y: f32,
+    depth: f32,
+    inv_w: f32,
+    world: Vec3,
+    normal: Vec3,
+}
+
+// Renders on the CPU into an in-memory RGBA buffer, for machines without a GPU. Lit surfaces
+// and tone mapping only: surface.frag without the globe's water, night lights and clouds,
+// and no shadow maps, stars, atmosphere or post passes.
+pub struct SoftwareBackend {
+    width: usize,
+    height: usize,
+    color: Vec<Vec3>,
+    // Reversed, nearer is greater and cleared to 0.
+    depth: Vec<f32>,
+    pixels: Vec<u8>,
+    meshes: Vec<SoftwareMesh>,
+    textures: Vec<SoftwareTexture>,
+    pipelines: Vec<PipelineDesc>,
+    view_projection: Mat4,
+    eye: Vec3,
+    lights: Vec<Light>,
+    wireframe: bool,
+    pass: Option<Pass>,
+    pub tone_mapper: ToneMapper,
+    pub exposure: Exposure,
+}
+
+impl SoftwareBackend {
+    pub fn new(width: u32, height: u32) -> Self {
+        let (width, height) = (width as usize, height as usize);
+
+        SoftwareBackend {
+            width,
+            height,
+            color: vec![Vec3::new(0.0, 0.0, 0.0); width * height],
+            depth: vec![0.0; width * height],
+            pixels: vec![0; width * height * 4],
+            meshes: Vec::new(),
+            textures: Vec::new(),
+            pipelines: Vec::new(),
+            view_projection: Mat4::identity(),
+            eye: Vec3::new(0.0, 0.0, 0.0),
+            lights: Vec::new(),
+            wireframe: false,
+            pass: None,
+            tone_mapper: ToneMapper::Agx,
+            exposure: Exposure::new(),
+        }
+    }
+
+    fn draw_triangle(&mut self, vertices: [ClipVertex; 3], draw: &DrawCall) {
+        let clipped = clip_near(&vertices);
+        if clipped.len() < 3 {
+            return;
+        }
+        let screen: Vec<ScreenVertex> = clipped.iter().map(|v| self.to_screen(v)).collect();
+        for i in 1..screen.len() - 1 {
+            self.rasterize([&screen[0], &screen[i], &screen[i + 1]], draw);
+        }
+    }
+
+    // Perspective divide and viewport transform, rows top to bottom.
+    fn to_screen(&self, vertex: &ClipVertex) -> ScreenVertex {
+        let [x, y, z, w] = vertex.clip;
+        let inv_w = 1.0 / w;
+        ScreenVertex {
+            x: (x * inv_w * 0.5 + 0.5) * self.width as f32,
+            y: (0.5 - y * inv_w * 0.5) * self.height as f32,
+            depth: z * inv_w,
+            inv_w,
+            world: vertex.world,
+            normal: vertex.normal,
+        }
+    }
+
+    fn rasterize(&mut self, v: [&ScreenVertex; 3], draw: &DrawCall) {
+        let area = edge(v[0], v[1], (v[2].x, v[2].y));
+        if area == 0.0 {
+            return;
+        }
+
+        let min_x = v.iter().map(|v| v.x).fold(f32::INFINITY, f32::min).max(0.0) as usize;
+        let min_y = v.iter().map(|v| v.y).fold(f32::INFINITY, f32::min).max(0.0) as usize;
+        let max_x = v
+            .iter()
+            .map(|v| v.x)
+            .fold(f32::NEG_INFINITY, f32::max)
+            .ceil()
+            .min(self.width as f32) as usize;
+        let max_y = v
+            .iter()
+            .map(|v| v.y)
+            .fold(f32::NEG_INFINITY, f32::max)
+            .ceil()
+            .min(self.height as f32) as usize;
+
+        // Length of the edge opposite each vertex, to measure distances to it in pixels.
+        let edge_lengths =
+            [(1, 2), (2, 0), (0, 1)].map(|(a, b)| (v[b].x - v[a].x).hypot(v[b].y - v[a].y));
+        let blended = self.pipelines[draw.pipeline.0].blended;
+
+        for y in min_y..max_y {
+            for x in min_x..max_x {
+                let p = (x as f32 + 0.5, y as f32 + 0.5);
+                let weights = [
+                    edge(v[1], v[2], p),
+                    edge(v[2], v[0], p),
+                    edge(v[0], v[1], p),
+                ]
+                .map(|e| e / area);
+                if weights.iter().any(|&w| w < 0.0) {
+                    continue;
+                }
+                if self.wireframe {
+                    let on_edge = (0..3)
+                        .any(|i| weights[i] * area.abs() / edge_lengths[i] <= LINE_HALF_WIDTH);
+                    if !on_edge {
+                        continue;
+                    }
+                }
+
+                let index = y * self.width + x;
+                let depth: f32 = (0..3).map(|i| weights[i] * v[i].depth).sum();
+                if depth <= self.depth[index] {
+                    continue;
+                }
+
+                // Screen space weights become perspective-correct through 1/w.
+                let perspective = [0, 1, 2].map(|i| weights[i] * v[i].inv_w);
+                let total: f32 = perspective.iter().sum();
+                let mut world = Vec3::new(0.0, 0.0, 0.0);
+                let mut normal = Vec3::new(0.0, 0.0, 0.0);
+                for i in 0..3 {
+                    world += v[i].world * (perspective[i] / total);
+                    normal += v[i].normal * (perspective[i] / total);
+                }
+
+                let (color, opacity) = self.shade(world, normal, draw);
+                if blended {
+                    self.color[index] = color * opacity + self.color[index] * (1.0 - opacity);
+                } else {
+                    self.color[index] = color;
+                    self.depth[index] = depth;
+                }
+            }
+        }
+    }
+
+    // surface.frag for a surface without the globe layers, before tone mapping.
+    fn shade(&self, world: Vec3, normal: Vec3, draw: &DrawCall) -> (Vec3, f32) {
+        let material = draw.material;
+        let n = normal.normalize();
+        let view_dir = (self.eye - world).normalize();
+
+        let mut albedo = material.base_color;
+        if let Some(texture) = material.base_color_map {
+            albedo = albedo * self.textures[texture.0].sample(globe_uv(n));
+        }
+
+        let mut result = albedo * 0.03;
+        for light in &self.lights {
+            let (to_light, radiance) = light_radiance(light, world);
+            result += cook_torrance(
+                n,
+                to_light,
+                view_dir,
+                radiance,
+                albedo,
+                material.roughness,
+                material.metalness,
+            );
+        }
+        (result + material.emissive, material.opacity)
+    }
+
+    // Exposure, tone mapping and sRGB encoding, as tonemap.frag. Automatic exposure meters
+    // the frame itself like luminance.frag and adapts at once, so every frame comes out as a
+    // settled one would.
+    fn resolve(&mut self) {
+        if self.exposure.auto {
+            let (log_sum, covered) = self
+                .color
+                .iter()
+                .map(|color| color.dot(&Vec3::new(0.2126, 0.7152, 0.0722)))
+                .filter(|luminance| *luminance >= MIN_METERED_LUMINANCE)
+                .fold((0.0, 0), |(sum, count), luminance| {
+                    (sum + luminance.ln(), count + 1)
+                });
+            let luminance = if covered > 0 {
+                (log_sum / covered as f32).exp()
+            } else {
+                0.0
+            };
+            self.exposure.adapt(luminance, f32::INFINITY);
+        }
+
+        let exposure = self.exposure.multiplier();
+        for (color, pixel) in self.color.iter().zip(self.pixels.chunks_exact_mut(4)) {
+            let color = *color * exposure;
+            let display = match self.tone_mapper {
+                ToneMapper::Reinhard => map(reinhard(color), linear_to_srgb),
+                ToneMapper::Aces => map(aces(color), linear_to_srgb),
+                ToneMapper::Agx => agx(color),
+            };
+            for (channel, value) in pixel.iter_mut().zip([display.x, display.y, display.z]) {
+                *channel = (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+            }
+            pixel[3] = 255;
+        }
+    }
+}
+
+impl RenderBackend for SoftwareBackend {
+    fn create_mesh(&mut self, mesh: &MeshData) -> Result<MeshId, BackendError> {
+        let (Some((3, position)), Some((3, normal))) = (
+            mesh.layout.attribute(Semantic::Position),
+            mesh.layout.attribute(Semantic::Normal),
+        ) else {
+            return Err(BackendError::Mesh(
+                "surfaces need three component positions and normals".to_string(),
+            ));
+        };
+
+        let vertices = mesh.vertices.chunks_exact(mesh.layout.floats_per_vertex());
+        let at = |vertex: &[f32], offset: usize| {
+            Vec3::new(vertex[offset], vertex[offset + 1], vertex[offset + 2])
+        };
+        self.meshes.push(SoftwareMesh {
+            positions: vertices
+                .clone()
+                .map(|vertex| at(vertex, position))
+                .collect(),
+            normals: vertices.map(|vertex| at(vertex, normal)).collect(),
+            indices: mesh.indices.clone(),
+        });
+        Ok(MeshId(self.meshes.len() - 1))
+    }
+
+    fn create_texture(&mut self, image: &ImageData) -> TextureId {
+        let decode = |value: u8| {
+            let value = value as f32 / 255.0;
+            if image.srgb {
+                srgb_to_linear(value)
+            } else {
+                value
+            }
+        };
+        self.textures.push(SoftwareTexture {
+            width: image.width as usize,
+            height: image.height as usize,
+            texels: image
+                .pixels
+                .chunks_exact(4)
+                .map(|texel| Vec3::new(decode(texel[0]), decode(texel[1]), decode(texel[2])))
+                .collect(),
+        });
+        TextureId(self.textures.len() - 1)
+    }
+
+    fn create_pipeline(&mut self, desc: &PipelineDesc) -> Result<PipelineId, BackendError> {
+        self.pipelines.push(*desc);
+        Ok(PipelineId(self.pipelines.len() - 1))
+    }
+
+    fn begin_pass(&mut self, pass: Pass, frame: &Frame) -> bool {
+        if pass == Pass::Shadow {
+            return false;
+        }
+
+        let camera = frame.camera;
+        let aspect = self.width as f32 / self.height as f32;
+        let (near, _) = clip_planes(camera.position);
+        let projection = reversed_infinite_perspective(camera.fov.to_radians(), aspect, near);
+        self.view_projection = &projection * &camera.get_view_matrix();
+        self.eye = camera.position;
+        self.lights = frame.lights.iter().take(MAX_LIGHTS).copied().collect();
+        self.wireframe = frame.wireframe;
+
+        self.color.fill(Vec3::new(0.0, 0.0, 0.0));
+        self.depth.fill(0.0);
+        self.pass = Some(pass);
+        true
+    }
+
+    fn draw(&mut self, draw: &DrawCall) {
+        if self.pass != Some(Pass::Scene) {
+            return;
+        }
+
+        let mesh = &self.meshes[draw.mesh.0];
+        let vertices: Vec<ClipVertex> = mesh
+            .positions
+            .iter()
+            .zip(&mesh.normals)
+            .map(|(position, normal)| ClipVertex {
+                clip: transform(&self.view_projection, *position),
+                world: *position,
+                normal: *normal,
+            })
+            .collect();
+        let triangles: Vec<[ClipVertex; 3]> = mesh
+            .indices
+            .chunks_exact(3)
+            .map(|triangle| [0, 1, 2].map(|corner| vertices[triangle[corner] as usize]))
+            .collect();
+
+        for triangle in triangles {
+            self.draw_triangle(triangle, draw);
+        }
+    }
+
+    fn end_pass(&mut self, _frame: &Frame) {
+        if self.pass.take() == Some(Pass::Scene) {
+            self.resolve();
+        }
+    }
+
+    fn toggle_effect(&mut self, effect: Effect) {
+        if effect == Effect::AutoExposure {
+            self.exposure.toggle_auto();
+        }
+    }
+
+    fn cycle_tone_mapper(&mut self) {
+        self.tone_mapper = self.tone_mapper.next();
+    }
+
+    fn adjust_exposure(&mut self, stops: f32) {
+        self.exposure.compensation += stops;
+    }
+
+    fn read_pixels(&mut self) -> Option<ImageData> {
+        Some(ImageData {
+            width: self.width as u32,
+            height: self.height as u32,
+            pixels: self.pixels.clone(),
+            srgb: true,
+        })
+    }
+}
+
+impl SoftwareTexture {
+    // Wraps around in u and clamps in v, like the globe's texture parameters.
+    fn sample(&self, (u, v): (f32, f32)) -> Vec3 {
+        let x = u.rem_euclid(1.0) * self.width as f32 - 0.5;
+        let y = v.clamp(0.0, 1.0) * self.height as f32 - 0.5;
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+
+        let texel = |x: f32, y: f32| {
+            let x = (x as isize).rem_euclid(self.width as isize) as usize;
+            let y = (y.max(0.0) as usize).min(self.height - 1);
+            self.texels[y * self.width + x]
+        };
+        let top = texel(x0, y0).lerp(&texel(x0 + 1.0, y0), fx);
+        let bottom = texel(x0, y0 + 1.0).lerp(&texel(x0 + 1.0, y0 + 1.0), fx);
+        top.lerp(&bottom, fy)
+    }
+}
+
+fn transform(m: &Mat4, p: Vec3) -> [f32; 4] {
+    [0, 1, 2, 3].map(|row| {
+        let r = m.row(row);
+        r[0] * p.x + r[1] * p.y + r[2] * p.z + r[3]
+    })
+}
+
+// Keeps the part of a triangle in front of the near plane, where depth <= 1.
+fn clip_near(vertices: &[ClipVertex; 3]) -> Vec<ClipVertex> {
+    let inside = |v: &ClipVertex| v.clip[3] - v.clip[2];
+    let mut clipped = Vec::with_capacity(4);
+    for i in 0..3 {
+        let (a, b) = (&vertices[i], &vertices[(i + 1) % 3]);
+        let (da, db) = (inside(a), inside(b));
+        if da >= 0.0 {
+            clipped.push(*a);
+        }
+        if (da >= 0.0) != (db >= 0.0) {
+            let t = da / (da - db);
+            clipped.push(ClipVertex {
+                clip: [0, 1, 2, 3].map(|k| a.clip[k] + (b.clip[k] - a.clip[k]) * t),
+                world: a.world.lerp(&b.world, t),
+                normal: a.normal.lerp(&b.normal, t),
+            });
+        }
+    }
+    clipped
+}
+
+// Twice the signed area of the triangle a, b, p.
+fn edge(a: &ScreenVertex, b: &ScreenVertex, p: (f32, f32)) -> f32 {
+    (b.x - a.x) * (p.1 - a.y) - (b.y - a.y) * (p.0 - a.x)
+}
+
+// Longitude 0 faces +z and the north pole is +y, see scene::geo.
+fn globe_uv(n: Vec3) -> (f32, f32) {
+    (
+        n.x.atan2(n.z) / (2.0 * PI) + 0.5,
+        0.5 - n.y.clamp(-1.0, 1.0).asin() / PI,
+    )
+}
+
+// include/lights.glsl: the direction towards the light and the radiance reaching `pos`.
+fn light_radiance(light: &Light, pos: Vec3) -> (Vec3, Vec3) {
+    let mut radiance = light.color * light.intensity;
+    if light.kind == LightKind::Directional {
+        return (light.direction.normalize(), radiance);
+    }
+
+    let offset = light.position - pos;
+    let dist = offset.length();
+    let to_light = offset * (1.0 / dist);
+    let att = light.attenuation;
+    radiance = radiance * (1.0 / (att.x + att.y * dist + att.z * dist * dist));
+
+    if light.kind == LightKind::Spot {
+        let theta = (-to_light).dot(&light.direction.normalize());
+        radiance = radiance
+            * smoothstep(
+                light.outer_angle.to_radians().cos(),
+                light.inner_angle.to_radians().cos(),
+                theta,
+            );
+    }
+    (to_light, radiance)
+}
+
+// include/brdf.glsl.
+fn cook_torrance(
+    n: Vec3,
+    to_light: Vec3,
+    view_dir: Vec3,
+    radiance: Vec3,
+    albedo: Vec3,
+    roughness: f32,
+    metalness: f32,
+) -> Vec3 {
+    let n_dot_l = n.dot(&to_light).max(0.0);
+    if n_dot_l <= 0.0 {
+        return Vec3::new(0.0, 0.0, 0.0);
+    }
+    let half_dir = (to_light + view_dir).normalize();
+    let n_dot_v = n.dot(&view_dir).max(1e-4);
+    let n_dot_h = n.dot(&half_dir).max(0.0);
+
+    let alpha = (roughness * roughness).max(1e-3);
+    let alpha2 = alpha * alpha;
+    let d = n_dot_h * n_dot_h * (alpha2 - 1.0) + 1.0;
+    let distribution = alpha2 / (PI * d * d);
+
+    let k = (roughness + 1.0) * (roughness + 1.0) / 8.0;
+    let geometry = n_dot_l / (n_dot_l * (1.0 - k) + k) * n_dot_v / (n_dot_v * (1.0 - k) + k);
+
+    let f0 = Vec3::new(0.04, 0.04, 0.04).lerp(&albedo, metalness);
+    let schlick = (1.0 - half_dir.dot(&view_dir).max(0.0)).powi(5);
+    let fresnel = f0 + (Vec3::new(1.0, 1.0, 1.0) - f0) * schlick;
+
+    let specular = fresnel * (distribution * geometry / (4.0 * n_dot_l * n_dot_v));
+    let diffuse = (Vec3::new(1.0, 1.0, 1.0) - fresnel) * albedo * ((1.0 - metalness) / PI);
+    (diffuse + specular) * radiance * n_dot_l
+}
+
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+fn map(v: Vec3, f: impl Fn(f32) -> f32) -> Vec3 {
+    Vec3::new(f(v.x), f(v.y), f(v.z))
+}
+
+fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(value: f32) -> f32 {
+    if value < 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+fn reinhard(color: Vec3) -> Vec3 {
+    let luminance = color.dot(&Vec3::new(0.2126, 0.7152, 0.0722));
+    color * (1.0 / (1.0 + luminance))
+}
+
+// Narkowicz's fit of the ACES filmic curve.
+fn aces(color: Vec3) -> Vec3 {
+    map(color * 0.6, |c| {
+        ((c * (2.51 * c + 0.03)) / (c * (2.43 * c + 0.59) + 0.14)).clamp(0.0, 1.0)
+    })
+}
+
+// Polynomial fit of the AgX base contrast curve, after Benjamin Wrensch.
+fn agx_contrast(x: f32) -> f32 {
+    let x2 = x * x;
+    let x4 = x2 * x2;
+    15.5 * x4 * x2 - 40.14 * x4 * x + 31.96 * x4 - 6.868 * x2 * x + 0.4298 * x2 + 0.1191 * x
+        - 0.00232
+}
+
+// Returns display-encoded values. The matrices are written as the GLSL columns.
+fn agx(color: Vec3) -> Vec3 {
+    const INSET: [[f32; 3]; 3] = [
+        [0.84247906, 0.042328242, 0.042375655],
+        [0.0784336, 0.87846864, 0.0784336],
+        [0.079223745, 0.07916613, 0.879143],
+    ];
+    const OUTSET: [[f32; 3]; 3] = [
+        [1.196879, -0.052896852, -0.052971636],
+        [-0.09802088, 1.1519031, -0.09804345],
+        [-0.09902974, -0.098961177, 1.1510737],
+    ];
+    const MIN_EV: f32 = -12.47393;
+    const MAX_EV: f32 = 4.026069;
+
+    let times = |m: &[[f32; 3]; 3], c: Vec3| {
+        Vec3::new(
+            m[0][0] * c.x + m[1][0] * c.y + m[2][0] * c.z,
+            m[0][1] * c.x + m[1][1] * c.y + m[2][1] * c.z,
+            m[0][2] * c.x + m[1][2] * c.y + m[2][2] * c.z,
+        )
+    };
+    let encoded = map(times(&INSET, color), |c| {
+        (c.max(1e-10).log2().clamp(MIN_EV, MAX_EV) - MIN_EV) / (MAX_EV - MIN_EV)
+    });
+    map(times(&OUTSET, map(encoded, agx_contrast)), |c| {
+        c.clamp(0.0, 1.0)
+    })
+}
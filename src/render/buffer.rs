@@ -0,0 +1,174 @@
+use crate::render::backend::MeshData;
+
+use gl::types::{GLenum, GLsizeiptr, GLuint};
+
+// A GL buffer object, deleted on drop. `target` is where it gets bound, e.g.
+// gl::ARRAY_BUFFER or gl::UNIFORM_BUFFER.
+pub struct Buffer {
+    id: GLuint,
+    target: GLenum,
+}
+
+impl Buffer {
+    pub fn new(target: GLenum) -> Self {
+        let mut id = 0;
+        unsafe {
+            gl::GenBuffers(1, &mut id);
+        }
+        Buffer { id, target }
+    }
+
+    // Creates the buffer's storage from `data`. Element buffers are recorded in the bound
+    // vertex array.
+    pub fn upload<T: Copy>(&self, data: &[T], usage: GLenum) {
+        self.bind();
+        unsafe {
+            gl::BufferData(
+                self.target,
+                size_of_val(data) as GLsizeiptr,
+                data.as_ptr() as *const _,
+                usage,
+            );
+        }
+    }
+
+    // Uninitialised storage of `size` bytes, filled later with `update`.
+    pub fn allocate(&self, size: usize, usage: GLenum) {
+        self.bind();
+        unsafe {
+            gl::BufferData(self.target, size as GLsizeiptr, std::ptr::null(), usage);
+        }
+    }
+
+    // Overwrites the start of the buffer, which must be at least as large as `data`.
+    pub fn update<T: Copy>(&self, data: &[T]) {
+        self.bind();
+        unsafe {
+            gl::BufferSubData(
+                self.target,
+                0,
+                size_of_val(data) as GLsizeiptr,
+                data.as_ptr() as *const _,
+            );
+        }
+    }
+
+    // Copies the start of the buffer into `data`, e.g. texels packed into a pixel pack buffer.
+    pub fn read<T: Copy>(&self, data: &mut [T]) {
+        self.bind();
+        unsafe {
+            gl::GetBufferSubData(
+                self.target,
+                0,
+                size_of_val(data) as GLsizeiptr,
+                data.as_mut_ptr() as *mut _,
+            );
+        }
+    }
+
+    pub fn bind(&self) {
+        unsafe {
+            gl::BindBuffer(self.target, self.id);
+        }
+    }
+
+    // Binds nothing to the buffer's target, e.g. so pixel reads go to client memory again.
+    pub fn unbind(&self) {
+        unsafe {
+            gl::BindBuffer(self.target, 0);
+        }
+    }
+
+    // For indexed targets such as uniform buffers: attaches the buffer to binding `index`.
+    pub fn bind_base(&self, index: u32) {
+        unsafe {
+            gl::BindBufferBase(self.target, index, self.id);
+        }
+    }
+}
+
+impl Drop for Buffer {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteBuffers(1, &self.id);
+        }
+    }
+}
+
+// A vertex array object, deleted on drop. It records the attribute setup and the element
+// buffer bound while it is bound.
+pub struct VertexArray {
+    id: GLuint,
+}
+
+impl VertexArray {
+    pub fn new() -> Self {
+        let mut id = 0;
+        unsafe {
+            gl::GenVertexArrays(1, &mut id);
+        }
+        VertexArray { id }
+    }
+
+    pub fn bind(&self) {
+        unsafe {
+            gl::BindVertexArray(self.id);
+        }
+    }
+
+    pub fn draw_arrays(&self, mode: GLenum, count: usize) {
+        self.bind();
+        unsafe {
+            gl::DrawArrays(mode, 0, count as i32);
+        }
+    }
+
+    // Draws `count` u32 indices from the element buffer recorded in this array.
+    pub fn draw_elements(&self, mode: GLenum, count: usize) {
+        self.bind();
+        unsafe {
+            gl::DrawElements(mode, count as i32, gl::UNSIGNED_INT, std::ptr::null());
+        }
+    }
+}
+
+impl Drop for VertexArray {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteVertexArrays(1, &self.id);
+        }
+    }
+}
+
+// Indexed triangles uploaded from a MeshData, with the attribute setup recorded.
+pub struct Mesh {
+    vertex_array: VertexArray,
+    // Referenced by the vertex array, kept alive with it.
+    _vertex_buffer: Buffer,
+    _index_buffer: Buffer,
+    index_count: usize,
+}
+
+impl Mesh {
+    pub fn new(data: &MeshData) -> Self {
+        let vertex_array = VertexArray::new();
+        vertex_array.bind();
+        let vertex_buffer = Buffer::new(gl::ARRAY_BUFFER);
+        vertex_buffer.upload(&data.vertices, gl::STATIC_DRAW);
+        let index_buffer = Buffer::new(gl::ELEMENT_ARRAY_BUFFER);
+        index_buffer.upload(&data.indices, gl::STATIC_DRAW);
+        data.layout.apply();
+
+        Mesh {
+            vertex_array,
+            _vertex_buffer: vertex_buffer,
+            _index_buffer: index_buffer,
+            index_count: data.indices.len(),
+        }
+    }
+
+    pub fn draw(&self) {
+        self.vertex_array
+            .draw_elements(gl::TRIANGLES, self.index_count);
+    }
+}
@@ -0,0 +1,29 @@
+use gl::types::GLsync;
+
+// A GL sync object, signalled once every command issued before it has completed. Deleted
+// on drop.
+pub struct Fence {
+    sync: GLsync,
+}
+
+impl Fence {
+    pub fn new() -> Self {
+        Fence {
+            sync: unsafe { gl::FenceSync(gl::SYNC_GPU_COMMANDS_COMPLETE, 0) },
+        }
+    }
+
+    // Checks without waiting. Flushes the commands so the fence is sure to get signalled.
+    pub fn is_signaled(&self) -> bool {
+        let status = unsafe { gl::ClientWaitSync(self.sync, gl::SYNC_FLUSH_COMMANDS_BIT, 0) };
+        status == gl::ALREADY_SIGNALED || status == gl::CONDITION_SATISFIED
+    }
+}
+
+impl Drop for Fence {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteSync(self.sync);
+        }
+    }
+}
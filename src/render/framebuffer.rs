@@ -0,0 +1,111 @@
+use crate::textures::texture::Texture;
+
+use gl::types::{GLenum, GLuint};
+
+// A GL framebuffer object, deleted on drop. The attached textures are owned elsewhere.
+pub struct Framebuffer {
+    id: GLuint,
+}
+
+impl Framebuffer {
+    pub fn new() -> Self {
+        let mut id = 0;
+        unsafe {
+            gl::GenFramebuffers(1, &mut id);
+        }
+        Framebuffer { id }
+    }
+
+    pub fn bind(&self) {
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.id);
+        }
+    }
+
+    // Leaves the framebuffer bound. `attachment` is e.g. gl::COLOR_ATTACHMENT0.
+    pub fn attach(&self, attachment: GLenum, texture: &Texture) {
+        self.bind();
+        unsafe {
+            gl::FramebufferTexture(gl::FRAMEBUFFER, attachment, texture.id(), 0);
+        }
+    }
+
+    // One layer of an array texture.
+    pub fn attach_layer(&self, attachment: GLenum, texture: &Texture, layer: u32) {
+        self.bind();
+        unsafe {
+            gl::FramebufferTextureLayer(gl::FRAMEBUFFER, attachment, texture.id(), 0, layer as i32);
+        }
+    }
+
+    // For depth-only targets.
+    pub fn disable_color(&self) {
+        self.bind();
+        unsafe {
+            gl::DrawBuffer(gl::NONE);
+            gl::ReadBuffer(gl::NONE);
+        }
+    }
+}
+
+impl Drop for Framebuffer {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteFramebuffers(1, &self.id);
+        }
+    }
+}
+
+// Offscreen colour target sampled by the following pass.
+pub struct RenderTarget {
+    pub width: u32,
+    pub height: u32,
+    framebuffer: Framebuffer,
+    color: Texture,
+}
+
+impl RenderTarget {
+    pub fn new(width: u32, height: u32, internal_format: GLenum) -> Self {
+        let color = Texture::new(gl::TEXTURE_2D);
+        color.image_2d(internal_format, width, height, gl::RGBA, gl::FLOAT, None);
+        color.set_filter(gl::LINEAR, gl::LINEAR);
+        color.set_wrap(gl::CLAMP_TO_EDGE);
+
+        let framebuffer = Framebuffer::new();
+        framebuffer.attach(gl::COLOR_ATTACHMENT0, &color);
+        bind_default();
+
+        RenderTarget {
+            width,
+            height,
+            framebuffer,
+            color,
+        }
+    }
+
+    pub fn bind(&self) {
+        self.framebuffer.bind();
+        unsafe {
+            gl::Viewport(0, 0, self.width as i32, self.height as i32);
+        }
+    }
+
+    pub fn bind_color_texture(&self, unit: u32) {
+        self.color.bind(unit);
+    }
+}
+
+// The window's framebuffer, without touching the viewport.
+pub fn bind_default() {
+    unsafe {
+        gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+    }
+}
+
+// The window's framebuffer, with the viewport covering its `width` by `height` pixels.
+pub fn bind_screen(width: u32, height: u32) {
+    bind_default();
+    unsafe {
+        gl::Viewport(0, 0, width as i32, height as i32);
+    }
+}
@@ -0,0 +1,33 @@
+use crate::render::buffer::VertexArray;
+
+// Empty vertex array for drawing the single screen-covering triangle generated in
+// fullscreen.vert, shared by every full-screen pass.
+pub struct FullscreenTriangle {
+    vertex_array: VertexArray,
+}
+
+impl FullscreenTriangle {
+    pub fn new() -> Self {
+        FullscreenTriangle {
+            vertex_array: VertexArray::new(),
+        }
+    }
+
+    // Blending is left to the caller, depth testing is off for the draw.
+    pub fn draw(&self) {
+        // The pass must stay filled in wireframe mode.
+        let mut polygon_mode: [gl::types::GLint; 2] = [0; 2];
+        unsafe {
+            gl::GetIntegerv(gl::POLYGON_MODE, polygon_mode.as_mut_ptr());
+            gl::PolygonMode(gl::FRONT_AND_BACK, gl::FILL);
+            gl::Disable(gl::DEPTH_TEST);
+        }
+
+        self.vertex_array.draw_arrays(gl::TRIANGLES, 3);
+
+        unsafe {
+            gl::Enable(gl::DEPTH_TEST);
+            gl::PolygonMode(gl::FRONT_AND_BACK, polygon_mode[0] as gl::types::GLenum);
+        }
+    }
+}
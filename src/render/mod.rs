@@ -0,0 +1,7 @@
+pub mod backend;
+pub mod buffer;
+pub mod fence;
+pub mod framebuffer;
+pub mod fullscreen;
+pub mod post;
+pub mod vertex;
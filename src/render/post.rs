@@ -0,0 +1,418 @@
+use crate::core::config::config_dir;
+use crate::render::framebuffer::{bind_screen, RenderTarget};
+use crate::render::fullscreen::FullscreenTriangle;
+use crate::scene::hdr::{HdrTarget, ToneMapping};
+use crate::shaders::error::ShaderError;
+use crate::shaders::program::ShaderProgram;
+use crate::textures::lut::Lut3d;
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+// Bloom works on scene radiance, so it always runs before tone mapping.
+#[derive(Clone, Copy)]
+pub struct BloomConfig {
+    // Luminance above which pixels start to bloom.
+    pub threshold: f32,
+    pub intensity: f32,
+    // Horizontal plus vertical blur rounds at half resolution.
+    pub iterations: u32,
+}
+
+// Passes applied to the tone mapped image, in the configured order.
+#[derive(Clone)]
+pub enum PassConfig {
+    Fxaa,
+    Vignette { strength: f32, radius: f32 },
+    ColorGrading { lut: PathBuf, strength: f32 },
+    Sharpen { strength: f32 },
+}
+
+pub struct PostConfig {
+    pub bloom: Option<BloomConfig>,
+    pub passes: Vec<PassConfig>,
+}
+
+impl Default for PostConfig {
+    fn default() -> Self {
+        PostConfig {
+            bloom: Some(BloomConfig {
+                threshold: 1.0,
+                intensity: 0.05,
+                iterations: 4,
+            }),
+            passes: vec![
+                PassConfig::Fxaa,
+                PassConfig::Vignette {
+                    strength: 0.3,
+                    radius: 0.8,
+                },
+            ],
+        }
+    }
+}
+
+impl PostConfig {
+    // A missing file keeps the default chain.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(contents) => parse_config(&contents),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(PostConfig::default()),
+            Err(err) => Err(err),
+        }
+    }
+}
+
+pub fn config_path() -> PathBuf {
+    config_dir().join("post.cfg")
+}
+
+struct Entry {
+    key: String,
+    value: String,
+    line: usize,
+}
+
+struct Section {
+    name: String,
+    entries: Vec<Entry>,
+}
+
+fn invalid_line(line: usize, message: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("line {}: {}", line, message),
+    )
+}
+
+// One `[pass]` section per pass, top to bottom, with optional `key = value` parameters.
+// `enabled = false` keeps a section around without running it.
+fn parse_config(contents: &str) -> io::Result<PostConfig> {
+    let mut sections: Vec<Section> = Vec::new();
+    for (index, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            sections.push(Section {
+                name: name.trim().to_string(),
+                entries: Vec::new(),
+            });
+            continue;
+        }
+
+        let section = sections
+            .last_mut()
+            .ok_or_else(|| invalid_line(index + 1, "value outside of a pass section"))?;
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| invalid_line(index + 1, "expected `key = value`"))?;
+        section.entries.push(Entry {
+            key: key.trim().to_string(),
+            value: value.trim().to_string(),
+            line: index + 1,
+        });
+    }
+
+    let mut config = PostConfig {
+        bloom: None,
+        passes: Vec::new(),
+    };
+    for section in sections {
+        add_pass(&mut config, section)?;
+    }
+    Ok(config)
+}
+
+fn add_pass(config: &mut PostConfig, mut section: Section) -> io::Result<()> {
+    let mut take = |key: &str| {
+        let index = section.entries.iter().position(|entry| entry.key == key)?;
+        Some(section.entries.remove(index))
+    };
+
+    if let Some(entry) = take("enabled") {
+        let enabled = entry
+            .value
+            .parse::<bool>()
+            .map_err(|_| invalid_line(entry.line, "expected true or false"))?;
+        if !enabled {
+            return Ok(());
+        }
+    }
+
+    let mut number = |key: &str, default: f32| match take(key) {
+        Some(entry) => entry
+            .value
+            .parse::<f32>()
+            .map_err(|_| invalid_line(entry.line, "invalid number")),
+        None => Ok(default),
+    };
+
+    match section.name.as_str() {
+        "bloom" => {
+            config.bloom = Some(BloomConfig {
+                threshold: number("threshold", 1.0)?,
+                intensity: number("intensity", 0.05)?,
+                iterations: number("iterations", 4.0)?.max(1.0) as u32,
+            })
+        }
+        "fxaa" => config.passes.push(PassConfig::Fxaa),
+        "vignette" => config.passes.push(PassConfig::Vignette {
+            strength: number("strength", 0.3)?,
+            radius: number("radius", 0.8)?,
+        }),
+        "color_grading" => {
+            let strength = number("strength", 1.0)?;
+            let lut = take("lut")
+                .map(|entry| PathBuf::from(entry.value))
+                .ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "[color_grading] needs a lut path",
+                    )
+                })?;
+            config
+                .passes
+                .push(PassConfig::ColorGrading { lut, strength });
+        }
+        "sharpen" => config.passes.push(PassConfig::Sharpen {
+            strength: number("strength", 0.3)?,
+        }),
+        name => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unknown pass [{}]", name),
+            ))
+        }
+    }
+
+    match section.entries.first() {
+        Some(entry) => Err(invalid_line(
+            entry.line,
+            &format!("unknown key `{}` for [{}]", entry.key, section.name),
+        )),
+        None => Ok(()),
+    }
+}
+
+struct Bloom {
+    config: BloomConfig,
+    bright_program: ShaderProgram,
+    blur_program: ShaderProgram,
+    composite_program: ShaderProgram,
+    targets: [RenderTarget; 2],
+}
+
+impl Bloom {
+    fn new(config: BloomConfig, width: u32, height: u32) -> Result<Self, ShaderError> {
+        Ok(Bloom {
+            config,
+            bright_program: post_program("bloom_bright.frag")?,
+            blur_program: post_program("bloom_blur.frag")?,
+            composite_program: post_program("bloom_composite.frag")?,
+            targets: Bloom::targets(width, height),
+        })
+    }
+
+    // Half the frame's resolution, at least a pixel across.
+    fn targets(width: u32, height: u32) -> [RenderTarget; 2] {
+        let half = || RenderTarget::new((width / 2).max(1), (height / 2).max(1), gl::RGBA16F);
+        [half(), half()]
+    }
+
+    // Bright parts of the scene, blurred at half resolution and added back onto it.
+    fn apply(&self, hdr: &HdrTarget, triangle: &FullscreenTriangle) {
+        let [a, b] = &self.targets;
+
+        a.bind();
+        self.bright_program.use_program();
+        hdr.bind_color_texture(0);
+        self.bright_program.set_sampler("source", 0);
+        self.bright_program
+            .set_float("threshold", self.config.threshold);
+        triangle.draw();
+
+        self.blur_program.use_program();
+        self.blur_program.set_sampler("source", 0);
+        for _ in 0..self.config.iterations {
+            b.bind();
+            a.bind_color_texture(0);
+            self.blur_program
+                .set_vec2("direction", 1.0 / a.width as f32, 0.0);
+            triangle.draw();
+
+            a.bind();
+            b.bind_color_texture(0);
+            self.blur_program
+                .set_vec2("direction", 0.0, 1.0 / b.height as f32);
+            triangle.draw();
+        }
+
+        hdr.bind_color_only();
+        self.composite_program.use_program();
+        a.bind_color_texture(0);
+        self.composite_program.set_sampler("source", 0);
+        self.composite_program
+            .set_float("intensity", self.config.intensity);
+        unsafe {
+            gl::Enable(gl::BLEND);
+            gl::BlendFunc(gl::ONE, gl::ONE);
+        }
+        triangle.draw();
+        unsafe {
+            gl::Disable(gl::BLEND);
+        }
+    }
+}
+
+struct ScreenPass {
+    config: PassConfig,
+    program: ShaderProgram,
+    lut: Option<Lut3d>,
+}
+
+impl ScreenPass {
+    // A pass whose LUT cannot be loaded is skipped, broken shaders are errors.
+    fn new(config: PassConfig) -> Result<Option<Self>, ShaderError> {
+        let (shader, lut) = match &config {
+            PassConfig::Fxaa => ("fxaa.frag", None),
+            PassConfig::Vignette { .. } => ("vignette.frag", None),
+            PassConfig::Sharpen { .. } => ("sharpen.frag", None),
+            PassConfig::ColorGrading { lut, .. } => match Lut3d::load(lut) {
+                Ok(lut) => ("color_grading.frag", Some(lut)),
+                Err(err) => {
+                    eprintln!("Could not load {}: {}", lut.display(), err);
+                    return Ok(None);
+                }
+            },
+        };
+
+        Ok(Some(ScreenPass {
+            config,
+            program: post_program(shader)?,
+            lut,
+        }))
+    }
+
+    fn apply(&self, source: &RenderTarget, triangle: &FullscreenTriangle) {
+        let program = &self.program;
+        program.use_program();
+        source.bind_color_texture(0);
+        program.set_sampler("source", 0);
+        let texel_size = [1.0 / source.width as f32, 1.0 / source.height as f32];
+
+        match &self.config {
+            PassConfig::Fxaa => program.set("texelSize", &texel_size),
+            PassConfig::Vignette { strength, radius } => {
+                program.set_float("strength", *strength);
+                program.set_float("radius", *radius);
+            }
+            PassConfig::ColorGrading { strength, .. } => {
+                if let Some(lut) = &self.lut {
+                    lut.bind(1);
+                    program.set_sampler("lut", 1);
+                    program.set_float("lutSize", lut.size as f32);
+                }
+                program.set_float("strength", *strength);
+            }
+            PassConfig::Sharpen { strength } => {
+                program.set("texelSize", &texel_size);
+                program.set_float("strength", *strength);
+            }
+        }
+
+        triangle.draw();
+    }
+}
+
+// Everything between the lit HDR scene and the window: bloom, tone mapping, then the
+// display passes ping-ponging between two LDR targets, the last one drawing to the screen.
+pub struct PostProcessing {
+    pub enabled: bool,
+    bloom: Option<Bloom>,
+    passes: Vec<ScreenPass>,
+    targets: [RenderTarget; 2],
+    triangle: FullscreenTriangle,
+}
+
+impl PostProcessing {
+    // `width` by `height` is the window's framebuffer, see `resize`.
+    pub fn new(config: &PostConfig, width: u32, height: u32) -> Result<Self, ShaderError> {
+        let mut passes = Vec::new();
+        for pass in config.passes.iter().cloned() {
+            passes.extend(ScreenPass::new(pass)?);
+        }
+
+        Ok(PostProcessing {
+            enabled: true,
+            bloom: config
+                .bloom
+                .map(|bloom| Bloom::new(bloom, width, height))
+                .transpose()?,
+            passes,
+            targets: PostProcessing::targets(width, height),
+            triangle: FullscreenTriangle::new(),
+        })
+    }
+
+    fn targets(width: u32, height: u32) -> [RenderTarget; 2] {
+        let ldr = || RenderTarget::new(width, height, gl::RGBA8);
+        [ldr(), ldr()]
+    }
+
+    // Recreates the intermediate targets for a new framebuffer size.
+    pub fn resize(&mut self, width: u32, height: u32) {
+        if let Some(bloom) = self.bloom.as_mut() {
+            bloom.targets = Bloom::targets(width, height);
+        }
+        self.targets = PostProcessing::targets(width, height);
+    }
+
+    pub fn toggle(&mut self) {
+        self.enabled = !self.enabled;
+    }
+
+    pub fn shader_programs(&mut self) -> Vec<&mut ShaderProgram> {
+        let mut programs = Vec::new();
+        if let Some(bloom) = self.bloom.as_mut() {
+            programs.push(&mut bloom.bright_program);
+            programs.push(&mut bloom.blur_program);
+            programs.push(&mut bloom.composite_program);
+        }
+        programs.extend(self.passes.iter_mut().map(|pass| &mut pass.program));
+        programs
+    }
+
+    pub fn render(&self, hdr: &HdrTarget, tone_mapping: &ToneMapping) {
+        let passes: &[ScreenPass] = if self.enabled { &self.passes } else { &[] };
+        if self.enabled {
+            if let Some(bloom) = &self.bloom {
+                bloom.apply(hdr, &self.triangle);
+            }
+        }
+
+        // The screen matches the HDR target, both follow the framebuffer size.
+        if passes.is_empty() {
+            bind_screen(hdr.width, hdr.height);
+        } else {
+            self.targets[0].bind();
+        }
+        tone_mapping.render(hdr, &self.triangle);
+
+        for (index, pass) in passes.iter().enumerate() {
+            if index + 1 == passes.len() {
+                bind_screen(hdr.width, hdr.height);
+            } else {
+                self.targets[(index + 1) % 2].bind();
+            }
+            pass.apply(&self.targets[index % 2], &self.triangle);
+        }
+    }
+}
+
+fn post_program(fragment: &str) -> Result<ShaderProgram, ShaderError> {
+    ShaderProgram::new("fullscreen.vert", fragment)
+}
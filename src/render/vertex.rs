@@ -0,0 +1,152 @@
+use crate::shaders::error::ShaderError;
+use crate::shaders::program::ShaderProgram;
+use crate::shaders::reflection::{type_name, vector_shape};
+
+use std::ptr;
+
+// What a vertex attribute holds. Each semantic has a fixed location, bound in every program
+// before linking, so one vertex array works with every program that draws the mesh.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Semantic {
+    Position,
+    Normal,
+    // Unit vector towards a point at infinity, e.g. a star.
+    Direction,
+    Color,
+    // Point sprite size in pixels.
+    Size,
+}
+
+impl Semantic {
+    pub const ALL: [Semantic; 5] = [
+        Semantic::Position,
+        Semantic::Normal,
+        Semantic::Direction,
+        Semantic::Color,
+        Semantic::Size,
+    ];
+
+    pub fn location(self) -> u32 {
+        self as u32
+    }
+
+    // Vertex shader input carrying this semantic.
+    pub fn attribute_name(self) -> &'static str {
+        match self {
+            Semantic::Position => "aPos",
+            Semantic::Normal => "aNormal",
+            Semantic::Direction => "aDirection",
+            Semantic::Color => "aColor",
+            Semantic::Size => "aSize",
+        }
+    }
+
+    fn from_attribute_name(name: &str) -> Option<Self> {
+        Semantic::ALL
+            .into_iter()
+            .find(|semantic| semantic.attribute_name() == name)
+    }
+}
+
+struct VertexAttribute {
+    semantic: Semantic,
+    components: i32,
+    // In floats from the start of the vertex.
+    offset: usize,
+}
+
+// Interleaved float vertices, described attribute by attribute in buffer order.
+pub struct VertexLayout {
+    attributes: Vec<VertexAttribute>,
+    // Floats per vertex.
+    stride: usize,
+}
+
+impl VertexLayout {
+    pub fn new() -> Self {
+        VertexLayout {
+            attributes: Vec::new(),
+            stride: 0,
+        }
+    }
+
+    pub fn with(mut self, semantic: Semantic, components: i32) -> Self {
+        self.attributes.push(VertexAttribute {
+            semantic,
+            components,
+            offset: self.stride,
+        });
+        self.stride += components as usize;
+        self
+    }
+
+    pub fn floats_per_vertex(&self) -> usize {
+        self.stride
+    }
+
+    // Component count and offset in floats of the attribute carrying `semantic`.
+    pub fn attribute(&self, semantic: Semantic) -> Option<(i32, usize)> {
+        self.attributes
+            .iter()
+            .find(|attribute| attribute.semantic == semantic)
+            .map(|attribute| (attribute.components, attribute.offset))
+    }
+
+    // Points the attributes at the buffer bound to GL_ARRAY_BUFFER, in the bound vertex array.
+    pub fn apply(&self) {
+        let stride = (self.stride * size_of::<gl::types::GLfloat>()) as gl::types::GLsizei;
+        for attribute in &self.attributes {
+            let location = attribute.semantic.location();
+            unsafe {
+                gl::VertexAttribPointer(
+                    location,
+                    attribute.components,
+                    gl::FLOAT,
+                    gl::FALSE,
+                    stride,
+                    if attribute.offset == 0 {
+                        ptr::null()
+                    } else {
+                        (attribute.offset * size_of::<gl::types::GLfloat>()) as *const _
+                    },
+                );
+                gl::EnableVertexAttribArray(location);
+            }
+        }
+    }
+
+    // Checks that the layout provides every input `program` reads, with matching sizes.
+    pub fn validate(&self, program: &ShaderProgram) -> Result<(), ShaderError> {
+        let error = |message: String| ShaderError::VertexLayout {
+            program: program.label(),
+            message,
+        };
+        for input in &program.reflection().attributes {
+            if input.name.starts_with("gl_") {
+                continue;
+            }
+            let semantic = Semantic::from_attribute_name(&input.name)
+                .ok_or_else(|| error(format!("input {} has no vertex semantic", input.name)))?;
+            let attribute = self
+                .attributes
+                .iter()
+                .find(|attribute| attribute.semantic == semantic)
+                .ok_or_else(|| {
+                    error(format!("the mesh has no {:?} for {}", semantic, input.name))
+                })?;
+            match vector_shape(input.kind) {
+                Some((components, gl::FLOAT)) if components == attribute.components => {}
+                _ => {
+                    return Err(error(format!(
+                        "{} is a {}, the mesh has {} floats of {:?}",
+                        input.name,
+                        type_name(input.kind),
+                        attribute.components,
+                        semantic
+                    )))
+                }
+            }
+        }
+        Ok(())
+    }
+}
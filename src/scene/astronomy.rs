@@ -0,0 +1,101 @@
+use crate::algebra::vec3::Vec3;
+
+const UNIX_EPOCH_JULIAN_DATE: f64 = 2_440_587.5;
+const J2000_JULIAN_DATE: f64 = 2_451_545.0;
+
+pub fn julian_date(unix_seconds: f64) -> f64 {
+    unix_seconds / 86_400.0 + UNIX_EPOCH_JULIAN_DATE
+}
+
+// Greenwich mean sidereal time in radians (IAU 1982, truncated).
+pub fn greenwich_sidereal_time(unix_seconds: f64) -> f64 {
+    let days = julian_date(unix_seconds) - J2000_JULIAN_DATE;
+    let centuries = days / 36_525.0;
+    let degrees =
+        280.460_618_37 + 360.985_647_366_29 * days + 0.000_387_933 * centuries * centuries;
+    degrees.rem_euclid(360.0).to_radians()
+}
+
+// Rotates an inertial (equatorial) direction into the Earth-fixed frame.
+pub fn inertial_to_ecef(direction: [f64; 3], unix_seconds: f64) -> [f64; 3] {
+    let (sin, cos) = greenwich_sidereal_time(unix_seconds).sin_cos();
+    [
+        direction[0] * cos + direction[1] * sin,
+        -direction[0] * sin + direction[1] * cos,
+        direction[2],
+    ]
+}
+
+// Low precision solar ephemeris from the Astronomical Almanac, good to about 0.01 degree.
+pub fn sun_direction_ecef(unix_seconds: f64) -> [f64; 3] {
+    let days = julian_date(unix_seconds) - J2000_JULIAN_DATE;
+    let mean_longitude = 280.460 + 0.985_647_4 * days;
+    let mean_anomaly = (357.528 + 0.985_600_3 * days).to_radians();
+    let ecliptic_longitude =
+        (mean_longitude + 1.915 * mean_anomaly.sin() + 0.020 * (2.0 * mean_anomaly).sin())
+            .to_radians();
+    let obliquity = (23.439 - 0.000_000_4 * days).to_radians();
+
+    let inertial = [
+        ecliptic_longitude.cos(),
+        obliquity.cos() * ecliptic_longitude.sin(),
+        obliquity.sin() * ecliptic_longitude.sin(),
+    ];
+    inertial_to_ecef(inertial, unix_seconds)
+}
+
+// ECEF has +x at (0N, 0E) and +z at the north pole; the scene has +z at (0N, 0E) and +y north.
+pub fn ecef_to_scene(v: [f64; 3]) -> Vec3 {
+    Vec3::new(v[1] as f32, v[2] as f32, v[0] as f32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 2000-01-01 12:00 UTC.
+    const J2000_UNIX_SECONDS: f64 = 946_728_000.0;
+
+    // Subsolar latitude and longitude in degrees.
+    fn subsolar_point(unix_seconds: f64) -> (f64, f64) {
+        let [x, y, z] = sun_direction_ecef(unix_seconds);
+        (z.atan2(x.hypot(y)).to_degrees(), y.atan2(x).to_degrees())
+    }
+
+    #[test]
+    fn sidereal_time_at_j2000() {
+        assert_eq!(julian_date(J2000_UNIX_SECONDS), J2000_JULIAN_DATE);
+        let degrees = greenwich_sidereal_time(J2000_UNIX_SECONDS).to_degrees();
+        assert!((degrees - 280.460_618).abs() < 1e-6, "{}", degrees);
+
+        // One solar day later the sky has turned a little less than a degree further.
+        let next = greenwich_sidereal_time(J2000_UNIX_SECONDS + 86_400.0).to_degrees();
+        assert!((next - degrees - 0.985_647).abs() < 1e-4, "{}", next);
+    }
+
+    #[test]
+    fn sun_over_the_tropics_at_the_solstices() {
+        // 2024-06-20 20:51 and 2024-12-21 09:20 UTC.
+        let (june, _) = subsolar_point(1_718_916_660.0);
+        assert!((june - 23.44).abs() < 0.05, "{}", june);
+        let (december, _) = subsolar_point(1_734_772_800.0);
+        assert!((december + 23.44).abs() < 0.05, "{}", december);
+    }
+
+    #[test]
+    fn sun_over_the_equator_at_the_equinoxes() {
+        // 2024-03-20 03:06 and 2024-09-22 12:44 UTC.
+        for unix_seconds in [1_710_903_960.0, 1_727_009_040.0] {
+            let (latitude, _) = subsolar_point(unix_seconds);
+            assert!(latitude.abs() < 0.05, "{} at {}", latitude, unix_seconds);
+        }
+    }
+
+    #[test]
+    fn sun_near_the_prime_meridian_at_noon_utc() {
+        // 2024-03-20 12:00 UTC. The sun crosses Greenwich about 7.5 minutes later that day (the
+        // equation of time), so it is still a little under two degrees east.
+        let (_, longitude) = subsolar_point(1_710_936_000.0);
+        assert!((longitude - 1.9).abs() < 0.5, "{}", longitude);
+    }
+}
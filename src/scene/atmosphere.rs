@@ -0,0 +1,98 @@
+use crate::algebra::vec3::Vec3;
+use crate::core::constants::{GLOBE_RADIUS, METERS_PER_UNIT};
+use crate::render::fullscreen::FullscreenTriangle;
+use crate::scene::camera::Camera;
+use crate::scene::depth::DepthMode;
+use crate::scene::hdr::HdrTarget;
+use crate::scene::light::Light;
+use crate::shaders::error::ShaderError;
+use crate::shaders::program::ShaderProgram;
+
+// Single-scattering Rayleigh/Mie atmosphere, ray marched per pixel in a full-screen pass
+// over the scene. Coefficients are per meter, heights in meters, at sea level.
+pub struct Atmosphere {
+    pub planet_radius: f32,
+    pub atmosphere_height: f32,
+    pub rayleigh_scattering: Vec3,
+    pub rayleigh_scale_height: f32,
+    pub mie_scattering: f32,
+    pub mie_scale_height: f32,
+    pub mie_anisotropy: f32,
+    shader_program: ShaderProgram,
+    triangle: FullscreenTriangle,
+}
+
+impl Atmosphere {
+    pub fn earth() -> Result<Self, ShaderError> {
+        let shader_program = ShaderProgram::new("fullscreen.vert", "atmosphere.frag")?;
+
+        Ok(Atmosphere {
+            planet_radius: GLOBE_RADIUS * METERS_PER_UNIT,
+            atmosphere_height: 60_000.0,
+            rayleigh_scattering: Vec3::new(5.8e-6, 13.5e-6, 33.1e-6),
+            rayleigh_scale_height: 8_000.0,
+            mie_scattering: 21e-6,
+            mie_scale_height: 1_200.0,
+            mie_anisotropy: 0.76,
+            shader_program,
+            triangle: FullscreenTriangle::new(),
+        })
+    }
+
+    pub fn shader_programs(&mut self) -> Vec<&mut ShaderProgram> {
+        vec![&mut self.shader_program]
+    }
+
+    pub fn render(
+        &self,
+        camera: &Camera,
+        light: &Light,
+        target: &HdrTarget,
+        depth_mode: DepthMode,
+        near: f32,
+        far: f32,
+    ) {
+        let program = &self.shader_program;
+        program.use_program();
+
+        program.set_vec3("viewPos", &camera.position);
+        program.set_vec3("cameraFront", &camera.front);
+        program.set_vec3("cameraRight", &camera.right);
+        program.set_vec3("cameraUp", &camera.up);
+        program.set_float("tanHalfFov", (camera.fov.to_radians() / 2.0).tan());
+        program.set_float("aspect", target.width as f32 / target.height as f32);
+
+        light.update_shader(program);
+
+        // The shader works in scene units, where the globe has radius GLOBE_RADIUS.
+        let scale = GLOBE_RADIUS / self.planet_radius;
+        program.set_float("planetRadius", GLOBE_RADIUS);
+        program.set_float(
+            "atmosphereRadius",
+            GLOBE_RADIUS + self.atmosphere_height * scale,
+        );
+        program.set_vec3(
+            "rayleighScattering",
+            &(self.rayleigh_scattering * (1.0 / scale)),
+        );
+        program.set_float("rayleighScaleHeight", self.rayleigh_scale_height * scale);
+        program.set_float("mieScattering", self.mie_scattering / scale);
+        program.set_float("mieScaleHeight", self.mie_scale_height * scale);
+        program.set_float("mieAnisotropy", self.mie_anisotropy);
+
+        target.bind_color_only();
+        target.bind_depth_texture(0);
+        program.set_sampler("sceneDepth", 0);
+        depth_mode.update_shader(program, far);
+        program.set_float("nearPlane", near);
+
+        unsafe {
+            gl::Enable(gl::BLEND);
+            gl::BlendFunc(gl::ONE, gl::SRC1_COLOR);
+        }
+        self.triangle.draw();
+        unsafe {
+            gl::Disable(gl::BLEND);
+        }
+    }
+}
@@ -0,0 +1,306 @@
+use crate::algebra::vec3::Vec3;
+use crate::core::config::config_dir;
+use crate::scene::camera::{CameraMode, ViewState};
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub const LAST_VIEW: &str = "last";
+
+pub struct Bookmarks {
+    path: PathBuf,
+    views: BTreeMap<String, ViewState>,
+    // Set when the file could not be read or set aside, so saving would overwrite it.
+    read_only: bool,
+}
+
+impl Bookmarks {
+    pub fn new(path: PathBuf) -> Self {
+        Bookmarks {
+            path,
+            views: BTreeMap::new(),
+            read_only: false,
+        }
+    }
+
+    // An empty set of views that is never written to `path`, for when the file there
+    // could not be loaded.
+    pub fn read_only(path: PathBuf) -> Self {
+        Bookmarks {
+            read_only: true,
+            ..Bookmarks::new(path)
+        }
+    }
+
+    // A file that does not parse is moved to a backup next to it before the error is
+    // returned, so the views it holds survive the next save.
+    pub fn load(path: PathBuf) -> io::Result<Self> {
+        let views = match fs::read_to_string(&path) {
+            Ok(contents) => match parse_views(&contents) {
+                Ok(views) => views,
+                Err(err) => {
+                    let backup = backup_path(&path);
+                    fs::rename(&path, &backup)?;
+                    return Err(io::Error::new(
+                        err.kind(),
+                        format!("{}, moved the file to {}", err, backup.display()),
+                    ));
+                }
+            },
+            Err(err) if err.kind() == io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(err) => return Err(err),
+        };
+
+        Ok(Bookmarks {
+            path,
+            views,
+            read_only: false,
+        })
+    }
+
+    // Loads the views at `path`, falling back to an empty set after reporting the error.
+    // The fallback is only saved when the unreadable file has been moved out of the way.
+    pub fn load_or_empty(path: PathBuf) -> Self {
+        Bookmarks::load(path.clone()).unwrap_or_else(|err| {
+            eprintln!("Could not load camera views: {}", err);
+            if path.exists() {
+                Bookmarks::read_only(path)
+            } else {
+                Bookmarks::new(path)
+            }
+        })
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        if self.read_only {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "the existing file could not be loaded, not overwriting it",
+            ));
+        }
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&self.path, format_views(&self.views))
+    }
+
+    pub fn get(&self, name: &str) -> Option<&ViewState> {
+        self.views.get(name)
+    }
+
+    pub fn set(&mut self, name: &str, view: ViewState) {
+        self.views.insert(name.to_string(), view);
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+pub fn default_path() -> PathBuf {
+    config_dir().join("views.cfg")
+}
+
+// `<path>.bak`, or `<path>.bak.N` with the first free N when earlier backups exist, so
+// none of them is overwritten.
+fn backup_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".bak");
+    let mut backup = path.with_file_name(&name);
+    let mut count = 1;
+    while backup.exists() {
+        let mut numbered = name.clone();
+        numbered.push(format!(".{}", count));
+        backup = path.with_file_name(numbered);
+        count += 1;
+    }
+    backup
+}
+
+// Views are stored as `[name]` sections followed by `key = value` lines.
+fn format_views(views: &BTreeMap<String, ViewState>) -> String {
+    let mut out = String::new();
+    for (name, view) in views {
+        let mode = match view.mode {
+            CameraMode::Free => "free",
+            CameraMode::Orbit => "orbit",
+        };
+        out.push_str(&format!("[{}]\n", name));
+        out.push_str(&format!(
+            "position = {} {} {}\n",
+            view.position.x, view.position.y, view.position.z
+        ));
+        out.push_str(&format!("yaw = {}\n", view.yaw));
+        out.push_str(&format!("pitch = {}\n", view.pitch));
+        out.push_str(&format!("fov = {}\n", view.fov));
+        out.push_str(&format!("mode = {}\n\n", mode));
+    }
+    out
+}
+
+fn parse_views(contents: &str) -> io::Result<BTreeMap<String, ViewState>> {
+    let mut views = BTreeMap::new();
+    let mut current: Option<(String, ViewState)> = None;
+
+    for (index, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            if let Some((name, view)) = current.take() {
+                views.insert(name, view);
+            }
+            current = Some((name.to_string(), default_view()));
+            continue;
+        }
+
+        let invalid = |message: &str| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("line {}: {}", index + 1, message),
+            )
+        };
+
+        let (_, view) = current
+            .as_mut()
+            .ok_or_else(|| invalid("value outside of a view section"))?;
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| invalid("expected `key = value`"))?;
+        let value = value.trim();
+        let number = |text: &str| text.parse::<f32>().map_err(|_| invalid("invalid number"));
+
+        match key.trim() {
+            "position" => {
+                let parts = value
+                    .split_whitespace()
+                    .map(number)
+                    .collect::<io::Result<Vec<f32>>>()?;
+                if parts.len() != 3 {
+                    return Err(invalid("position needs three components"));
+                }
+                view.position = Vec3::new(parts[0], parts[1], parts[2]);
+            }
+            "yaw" => view.yaw = number(value)?,
+            "pitch" => view.pitch = number(value)?,
+            "fov" => view.fov = number(value)?,
+            "mode" => {
+                view.mode = match value {
+                    "free" => CameraMode::Free,
+                    "orbit" => CameraMode::Orbit,
+                    _ => return Err(invalid("unknown camera mode")),
+                }
+            }
+            _ => return Err(invalid("unknown key")),
+        }
+    }
+
+    if let Some((name, view)) = current {
+        views.insert(name, view);
+    }
+
+    Ok(views)
+}
+
+fn default_view() -> ViewState {
+    ViewState {
+        position: Vec3::new(0.0, 0.0, 3.0),
+        yaw: -90.0,
+        pitch: 0.0,
+        fov: 45.0,
+        mode: CameraMode::Free,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::env;
+
+    fn temp_path(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("earth-gl-bookmarks-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir.join(name)
+    }
+
+    #[test]
+    fn views_round_trip() {
+        let path = temp_path("round_trip.cfg");
+        let mut bookmarks = Bookmarks::new(path.clone());
+        let view = ViewState {
+            position: Vec3::new(1.5, -2.0, 0.25),
+            yaw: 12.0,
+            pitch: -30.0,
+            fov: 60.0,
+            mode: CameraMode::Orbit,
+        };
+        bookmarks.set("1", view);
+        bookmarks.save().unwrap();
+
+        let loaded = Bookmarks::load(path.clone()).unwrap();
+        let restored = loaded.get("1").unwrap();
+        let p = restored.position;
+        assert_eq!((p.x, p.y, p.z), (1.5, -2.0, 0.25));
+        assert_eq!(
+            (restored.yaw, restored.pitch, restored.fov),
+            (view.yaw, view.pitch, view.fov)
+        );
+        assert!(restored.mode == CameraMode::Orbit);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn unparsable_file_is_kept_aside() {
+        let path = temp_path("broken.cfg");
+        let contents = "[1]\nposition = 1 2\n";
+        fs::write(&path, contents).unwrap();
+
+        let mut bookmarks = Bookmarks::load_or_empty(path.clone());
+        assert!(bookmarks.get("1").is_none());
+        bookmarks.set(LAST_VIEW, default_view());
+        bookmarks.save().unwrap();
+
+        let backup = path.with_file_name("broken.cfg.bak");
+        assert_eq!(fs::read_to_string(&backup).unwrap(), contents);
+        assert!(Bookmarks::load(path.clone())
+            .unwrap()
+            .get(LAST_VIEW)
+            .is_some());
+        fs::remove_file(path).unwrap();
+        fs::remove_file(backup).unwrap();
+    }
+
+    #[test]
+    fn earlier_backups_are_not_overwritten() {
+        let path = temp_path("broken_again.cfg");
+        let backups = ["bak", "bak.1", "bak.2"]
+            .map(|suffix| path.with_file_name(format!("broken_again.cfg.{}", suffix)));
+        fs::write(&backups[0], "first").unwrap();
+        fs::write(&backups[1], "second").unwrap();
+        fs::write(&path, "third").unwrap();
+
+        assert!(Bookmarks::load(path.clone()).is_err());
+        assert!(!path.exists());
+        for (backup, contents) in backups.iter().zip(["first", "second", "third"]) {
+            assert_eq!(fs::read_to_string(backup).unwrap(), contents);
+            fs::remove_file(backup).unwrap();
+        }
+    }
+
+    #[test]
+    fn read_only_views_are_not_saved() {
+        let path = temp_path("read_only.cfg");
+        fs::write(&path, "[1]\n").unwrap();
+
+        let mut bookmarks = Bookmarks::read_only(path.clone());
+        bookmarks.set(LAST_VIEW, default_view());
+        assert!(bookmarks.save().is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "[1]\n");
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -1,5 +1,17 @@
 use crate::algebra::mat4::Mat4;
 use crate::algebra::vec3::Vec3;
+use crate::core::constants::GLOBE_RADIUS;
+use crate::scene::depth::{log_depth_coefficient, DepthMode, LOG_DEPTH_SCALE};
+use crate::scene::flight::{Easing, Flight};
+use crate::scene::geo::{geodetic_to_cartesian, local_frame};
+use crate::shaders::uniform_buffer::{Std140, UniformBuffer};
+
+// Degrees per second per unit of movement speed when orbiting with A/D.
+const ORBIT_SPEED: f32 = 30.0;
+pub const CAMERA_BINDING: u32 = 1;
+// Two matrices, the eye position with the log depth coefficient, and the log depth flag
+// with its scale.
+const CAMERA_FLOATS: usize = 16 + 16 + 4 + 4;
 
 pub enum CameraMovement {
     Forward,
@@ -8,6 +20,21 @@ pub enum CameraMovement {
     Right,
 }
 
+#[derive(Clone, Copy, PartialEq)]
+pub enum CameraMode {
+    Free,
+    Orbit,
+}
+
+#[derive(Clone, Copy)]
+pub struct ViewState {
+    pub position: Vec3,
+    pub yaw: f32,
+    pub pitch: f32,
+    pub fov: f32,
+    pub mode: CameraMode,
+}
+
 pub struct Camera {
     pub position: Vec3,
     pub front: Vec3,
@@ -16,9 +43,13 @@ pub struct Camera {
     pub world_up: Vec3,
     pub yaw: f32,
     pub pitch: f32,
+    pub fov: f32,
+    pub mode: CameraMode,
     pub movement_speed: f32,
     pub mouse_sensitivity: f32,
     pub velocity: Vec3,
+    pub flight_easing: Easing,
+    flight: Option<Flight>,
 }
 
 impl Camera {
@@ -31,9 +62,13 @@ impl Camera {
             world_up,
             yaw: -90.0,
             pitch: 0.0,
+            fov: 45.0,
+            mode: CameraMode::Free,
             movement_speed: 2.0,
             mouse_sensitivity: 0.1,
             velocity: Vec3::new(0.0, 0.0, 0.0),
+            flight_easing: Easing::EaseInOutCubic,
+            flight: None,
         };
         camera.update_camera_vectors();
         camera
@@ -43,7 +78,82 @@ impl Camera {
         look_at(self.position, self.position + self.front, self.up)
     }
 
+    pub fn fly_to(
+        &mut self,
+        lat: f32,
+        lon: f32,
+        height: f32,
+        heading: f32,
+        pitch: f32,
+        duration: f32,
+    ) {
+        let target = geodetic_to_cartesian(lat, lon, height);
+        self.flight = Some(Flight::new(
+            self.position,
+            self.front,
+            target,
+            heading,
+            pitch,
+            duration,
+            self.flight_easing,
+        ));
+    }
+
+    pub fn cancel_flight(&mut self) {
+        self.flight = None;
+    }
+
+    pub fn view_state(&self) -> ViewState {
+        ViewState {
+            position: self.position,
+            yaw: self.yaw,
+            pitch: self.pitch,
+            fov: self.fov,
+            mode: self.mode,
+        }
+    }
+
+    pub fn set_view_state(&mut self, view: &ViewState) {
+        self.cancel_flight();
+        self.position = view.position;
+        self.yaw = view.yaw;
+        self.pitch = view.pitch;
+        self.fov = view.fov;
+        self.mode = view.mode;
+        self.update_camera_vectors();
+    }
+
+    pub fn fly_to_view(&mut self, view: &ViewState, duration: f32) {
+        let front = direction_from_angles(view.yaw, view.pitch);
+        let (heading, pitch) = local_frame(view.position).heading_pitch(front);
+        self.fov = view.fov;
+        self.mode = view.mode;
+        self.flight = Some(Flight::new(
+            self.position,
+            self.front,
+            view.position,
+            heading,
+            pitch,
+            duration,
+            self.flight_easing,
+        ));
+    }
+
+    pub fn toggle_mode(&mut self) {
+        self.mode = match self.mode {
+            CameraMode::Free => CameraMode::Orbit,
+            CameraMode::Orbit => CameraMode::Free,
+        };
+        if self.mode == CameraMode::Orbit {
+            self.set_front(-self.position);
+        }
+    }
+
     pub fn process_keyboard(&mut self, direction: CameraMovement, pressed: bool) {
+        if pressed {
+            self.cancel_flight();
+        }
+
         let speed = if pressed { self.movement_speed } else { 0.0 };
         match direction {
             CameraMovement::Forward => self.velocity.z = speed,
@@ -54,11 +164,34 @@ impl Camera {
     }
 
     pub fn update(&mut self, delta_time: f32) {
-        self.position += self.front * self.velocity.z * delta_time;
-        self.position += self.right * self.velocity.x * delta_time;
+        if let Some(flight) = self.flight.as_mut() {
+            let (position, front) = flight.step(delta_time);
+            if flight.is_finished() {
+                self.flight = None;
+            }
+            self.position = position;
+            self.set_front(front);
+            return;
+        }
+
+        match self.mode {
+            CameraMode::Free => {
+                self.position += self.front * self.velocity.z * delta_time;
+                self.position += self.right * self.velocity.x * delta_time;
+            }
+            CameraMode::Orbit => {
+                let distance = self.position.length() - self.velocity.z * delta_time;
+                let distance = distance.max(GLOBE_RADIUS * 1.01);
+                self.yaw -= self.velocity.x * delta_time * ORBIT_SPEED;
+                self.update_camera_vectors();
+                self.position = -self.front * distance;
+            }
+        }
     }
 
     pub fn process_mouse_movement(&mut self, x_offset: f32, y_offset: f32, constrain_pitch: bool) {
+        self.cancel_flight();
+
         let x_offset = x_offset * self.mouse_sensitivity;
         let y_offset = y_offset * self.mouse_sensitivity;
 
@@ -70,20 +203,70 @@ impl Camera {
         }
 
         self.update_camera_vectors();
+        if self.mode == CameraMode::Orbit {
+            self.position = -self.front * self.position.length();
+        }
+    }
+
+    pub fn set_front(&mut self, front: Vec3) {
+        let front = front.normalize();
+        self.yaw = front.z.atan2(front.x).to_degrees();
+        self.pitch = front
+            .y
+            .clamp(-1.0, 1.0)
+            .asin()
+            .to_degrees()
+            .clamp(-89.0, 89.0);
+        self.update_camera_vectors();
     }
 
     fn update_camera_vectors(&mut self) {
-        let front = Vec3::new(
-            self.yaw.to_radians().cos() * self.pitch.to_radians().cos(),
-            self.pitch.to_radians().sin(),
-            self.yaw.to_radians().sin() * self.pitch.to_radians().cos(),
-        );
-        self.front = front.normalize();
+        self.front = direction_from_angles(self.yaw, self.pitch);
         self.right = self.front.cross(&self.world_up).normalize();
         self.up = self.right.cross(&self.front).normalize();
     }
 }
 
+fn direction_from_angles(yaw: f32, pitch: f32) -> Vec3 {
+    Vec3::new(
+        yaw.to_radians().cos() * pitch.to_radians().cos(),
+        pitch.to_radians().sin(),
+        yaw.to_radians().sin() * pitch.to_radians().cos(),
+    )
+    .normalize()
+}
+
+// The `Camera` uniform block shared by every program that draws the scene in 3D.
+pub struct CameraBuffer {
+    buffer: UniformBuffer,
+}
+
+impl CameraBuffer {
+    pub fn new() -> Self {
+        CameraBuffer {
+            buffer: UniformBuffer::new(CAMERA_FLOATS, CAMERA_BINDING),
+        }
+    }
+
+    pub fn upload(
+        &self,
+        view: &Mat4,
+        projection: &Mat4,
+        position: &Vec3,
+        depth_mode: DepthMode,
+        far: f32,
+    ) {
+        let mut data = Std140::new();
+        data.mat4(view)
+            .mat4(projection)
+            .vec3(position)
+            .float(log_depth_coefficient(far))
+            .bool(depth_mode == DepthMode::Logarithmic)
+            .float(LOG_DEPTH_SCALE);
+        self.buffer.update(data.as_slice());
+    }
+}
+
 pub fn perspective(fov_y: f32, aspect: f32, near: f32, far: f32) -> Mat4 {
     let f = 1.0 / (fov_y / 2.0).tan();
     Mat4::new([
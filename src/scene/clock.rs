@@ -0,0 +1,152 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const MAX_SPEED: f64 = 100_000.0;
+
+pub struct SimulationClock {
+    pub unix_seconds: f64,
+    pub speed: f64,
+    pub paused: bool,
+}
+
+impl SimulationClock {
+    pub fn now() -> Self {
+        let unix_seconds = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs_f64())
+            .unwrap_or(0.0);
+
+        SimulationClock {
+            unix_seconds,
+            speed: 1.0,
+            paused: false,
+        }
+    }
+
+    // Returns how many simulated seconds went by.
+    pub fn update(&mut self, delta_time: f32) -> f64 {
+        if self.paused {
+            return 0.0;
+        }
+
+        let simulated = delta_time as f64 * self.speed;
+        self.unix_seconds += simulated;
+        simulated
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+    }
+
+    pub fn speed_up(&mut self) {
+        self.speed = (self.speed * 10.0).min(MAX_SPEED);
+    }
+
+    pub fn slow_down(&mut self) {
+        self.speed = (self.speed / 10.0).max(1.0);
+    }
+
+    pub fn set_date(
+        &mut self,
+        year: i64,
+        month: u32,
+        day: u32,
+        hour: u32,
+        minute: u32,
+        second: u32,
+    ) {
+        let days = days_from_civil(year, month, day);
+        self.unix_seconds =
+            (days * 86_400 + hour as i64 * 3_600 + minute as i64 * 60 + second as i64) as f64;
+    }
+
+    // Parses `YYYY-MM-DD` or `YYYY-MM-DDTHH:MM:SS`, always in UTC.
+    pub fn set_date_str(&mut self, text: &str) -> Result<(), String> {
+        let invalid = || format!("invalid date `{}`, expected YYYY-MM-DDTHH:MM:SS", text);
+        let (date, time) = text.split_once('T').unwrap_or((text, "00:00:00"));
+
+        let date = date
+            .splitn(3, '-')
+            .map(|part| part.parse::<i64>().map_err(|_| invalid()))
+            .collect::<Result<Vec<i64>, String>>()?;
+        let time = time
+            .trim_end_matches('Z')
+            .split(':')
+            .map(|part| part.parse::<u32>().map_err(|_| invalid()))
+            .collect::<Result<Vec<u32>, String>>()?;
+        if date.len() != 3 || time.len() != 3 {
+            return Err(invalid());
+        }
+        if !(1..=12).contains(&date[1])
+            || !(1..=days_in_month(date[0], date[1] as u32) as i64).contains(&date[2])
+            || time[0] >= 24
+            || time[1] >= 60
+            || time[2] >= 60
+        {
+            return Err(invalid());
+        }
+
+        self.set_date(
+            date[0],
+            date[1] as u32,
+            date[2] as u32,
+            time[0],
+            time[1],
+            time[2],
+        );
+        Ok(())
+    }
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// Days since 1970-01-01 in the proleptic Gregorian calendar (Howard Hinnant's algorithm).
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = month as i64;
+    let day_of_year =
+        (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> Result<f64, String> {
+        let mut clock = SimulationClock::now();
+        clock.set_date_str(text).map(|_| clock.unix_seconds)
+    }
+
+    #[test]
+    fn parses_dates_and_times() {
+        assert_eq!(parse("1970-01-01"), Ok(0.0));
+        assert_eq!(parse("2000-03-01T12:30:15Z"), Ok(951_913_815.0));
+        assert_eq!(parse("2024-02-29T23:59:59"), Ok(1_709_251_199.0));
+    }
+
+    #[test]
+    fn rejects_out_of_range_fields() {
+        for text in [
+            "2024-13-01",
+            "2024-04-31",
+            "2023-02-29",
+            "1900-02-29",
+            "2024-01-00",
+            "2024-01-01T24:00:00",
+            "2024-01-01T12:60:00",
+            "2024-01-01T12:00:60",
+        ] {
+            assert!(parse(text).is_err(), "{} was accepted", text);
+        }
+    }
+}
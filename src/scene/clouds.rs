@@ -0,0 +1,122 @@
+use crate::algebra::mat4::Mat4;
+use crate::core::constants::{GLOBE_RADIUS, METERS_PER_UNIT};
+use crate::objects::objects::Position;
+use crate::objects::sphere::{Sphere, SphereResolution};
+use crate::render::buffer::Mesh;
+use crate::scene::camera::CAMERA_BINDING;
+use crate::scene::light::Light;
+use crate::shaders::error::ShaderError;
+use crate::shaders::program::ShaderProgram;
+use crate::textures::texture::Texture;
+
+use std::f32::consts::PI;
+use std::path::Path;
+
+// Transparent shell above the globe textured with cloud cover, drifting on its own.
+pub struct CloudLayer {
+    pub visible: bool,
+    // Radians per simulated second around the polar axis.
+    pub rotation_speed: f32,
+    pub shadow_strength: f32,
+    rotation: f32,
+    radius: f32,
+    shell: Mesh,
+    texture: Texture,
+    shader_program: ShaderProgram,
+}
+
+impl CloudLayer {
+    // No layer without the cloud texture.
+    pub fn load(path: &Path) -> Result<Option<Self>, ShaderError> {
+        let Some(texture) = Texture::load_optional(path) else {
+            return Ok(None);
+        };
+        let radius = GLOBE_RADIUS + 10_000.0 / METERS_PER_UNIT;
+        let shell = Sphere::new(
+            radius,
+            Position {
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+            },
+            SphereResolution {
+                sectors: 128,
+                stacks: 128,
+            },
+        );
+
+        let mut shader_program = ShaderProgram::new("clouds.vert", "clouds.frag")?;
+        shader_program.bind_uniform_block("Camera", CAMERA_BINDING);
+        shell.mesh.layout.validate(&shader_program)?;
+
+        Ok(Some(CloudLayer {
+            visible: true,
+            rotation_speed: 2.0 * PI / (10.0 * 86_400.0),
+            shadow_strength: 0.6,
+            rotation: 0.0,
+            radius,
+            shell: Mesh::new(&shell.mesh),
+            texture,
+            shader_program,
+        }))
+    }
+
+    pub fn update(&mut self, simulated_seconds: f64) {
+        self.rotation =
+            (self.rotation + self.rotation_speed * simulated_seconds as f32).rem_euclid(2.0 * PI);
+    }
+
+    pub fn shader_programs(&mut self) -> Vec<&mut ShaderProgram> {
+        vec![&mut self.shader_program]
+    }
+
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+    }
+
+    // Uniforms the globe shader needs to cast the cloud shadows on the ground.
+    pub fn update_shader(&self, shader: &ShaderProgram, unit: u32) {
+        shader.set_bool("hasClouds", self.visible);
+        if !self.visible {
+            return;
+        }
+
+        self.texture.bind(unit);
+        shader.set_sampler("clouds", unit);
+        shader.set_float("cloudRadius", self.radius);
+        shader.set_float("cloudRotation", self.rotation);
+        shader.set_float("cloudShadowStrength", self.shadow_strength);
+    }
+
+    pub fn render(&self, light: &Light, eye_distance: f32) {
+        if !self.visible {
+            return;
+        }
+
+        let program = &self.shader_program;
+        program.use_program();
+        program.set_mat4("model", &Mat4::rotation_y(self.rotation));
+        light.update_shader(program);
+        self.texture.bind(0);
+        program.set_sampler("clouds", 0);
+
+        unsafe {
+            // Only the near side of the shell is visible, from above or from below.
+            gl::Enable(gl::CULL_FACE);
+            gl::CullFace(if eye_distance > self.radius {
+                gl::BACK
+            } else {
+                gl::FRONT
+            });
+            gl::Enable(gl::BLEND);
+            gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
+        }
+
+        self.shell.draw();
+
+        unsafe {
+            gl::Disable(gl::BLEND);
+            gl::Disable(gl::CULL_FACE);
+        }
+    }
+}
@@ -1,83 +1,302 @@
-use crate::algebra::mat4::Mat4;
 use crate::algebra::vec3::Vec3;
-use crate::core::constants::{HEIGHT, WIDTH};
+use crate::core::constants::{GLOBE_RADIUS, HEIGHT, WIDTH};
+use crate::objects::material::Shading;
 use crate::objects::objects::Object;
-use crate::scene::camera::{perspective, Camera, CameraMovement};
+use crate::render::backend::{
+    BackendError, DrawCall, Effect, Frame, MeshId, Pass, PipelineDesc, PipelineId, RenderBackend,
+    TextureId,
+};
+use crate::scene::astronomy::{ecef_to_scene, sun_direction_ecef};
+use crate::scene::camera::{perspective, Camera, CameraMovement, ViewState};
+use crate::scene::clock::SimulationClock;
+use crate::scene::culling::{Frustum, RenderStats};
+use crate::scene::depth::clip_planes;
+use crate::scene::geo::Ellipsoid;
 use crate::scene::light::Light;
-use crate::shaders::program::ShaderProgram;
+use crate::scene::recording::{Playback, Recorder};
+use crate::textures::image::ImageData;
 
 use std::f32::consts::PI;
+use std::io;
+use std::path::Path;
+use std::rc::Rc;
+
+// An object with its mesh uploaded to the backend.
+pub struct Instance {
+    pub object: Box<dyn Object>,
+    pub mesh: MeshId,
+}
 
 pub struct Scene {
-    shader_program: ShaderProgram,
-    pub instances: Vec<Box<dyn Object>>,
+    backend: Box<dyn RenderBackend>,
+    pipelines: Vec<(PipelineDesc, PipelineId)>,
+    pub instances: Vec<Instance>,
     pub camera: Camera,
-    light: Light,
+    sun: Light,
+    pub lights: Vec<Light>,
+    pub clock: SimulationClock,
+    elapsed: f32,
+    globe: Ellipsoid,
+    wireframe: bool,
+    // Of the image the backend draws, for culling.
+    aspect: f32,
+    recorder: Option<Recorder>,
+    playback: Option<Playback>,
 }
 
 impl Scene {
-    pub fn new() -> Self {
-        let shader_program =
-            ShaderProgram::new("src/shaders/shader.vert", "src/shaders/shader.frag");
-        let instances = Vec::new();
+    pub fn new(mut backend: Box<dyn RenderBackend>) -> Result<Self, BackendError> {
+        let mut pipelines = Vec::new();
+        for shading in [Shading::Globe, Shading::Standard] {
+            for blended in [false, true] {
+                let desc = PipelineDesc { shading, blended };
+                pipelines.push((desc, backend.create_pipeline(&desc)?));
+            }
+        }
         let camera = Camera::new(Vec3::new(0.0, 0.0, 3.0), Vec3::new(0.0, 1.0, 0.0));
 
-        let light = Light::new(Vec3::new(1.0, 1.0, 2.0), Vec3::new(1.0, 1.0, 1.0));
+        let clock = SimulationClock::now();
+        // Solar irradiance in the same units as the atmosphere's in-scattering.
+        let sun = Light {
+            intensity: 20.0,
+            ..Light::directional(
+                ecef_to_scene(sun_direction_ecef(clock.unix_seconds)),
+                Vec3::new(1.0, 1.0, 1.0),
+            )
+        };
 
-        Scene {
-            shader_program,
-            instances,
+        Ok(Scene {
+            backend,
+            pipelines,
+            instances: Vec::new(),
             camera,
-            light,
-        }
+            sun,
+            lights: Vec::new(),
+            clock,
+            elapsed: 0.0,
+            globe: Ellipsoid::sphere(GLOBE_RADIUS),
+            wireframe: false,
+            aspect: WIDTH as f32 / HEIGHT as f32,
+            recorder: None,
+            playback: None,
+        })
     }
 
     pub fn update(&mut self, delta_time: f32) {
-        self.camera.update(delta_time);
-    }
+        self.elapsed += delta_time;
 
-    pub fn render(&self) {
-        unsafe {
-            gl::ClearColor(0.1, 0.1, 0.1, 1.0);
-            gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+        let simulated_seconds = self.clock.update(delta_time);
+        self.sun
+            .set_direction(ecef_to_scene(sun_direction_ecef(self.clock.unix_seconds)));
+
+        if let Some(playback) = self.playback.as_mut() {
+            playback.step(&mut self.camera);
+            if playback.is_finished() {
+                self.playback = None;
+            }
+        } else {
+            self.camera.update(delta_time);
         }
 
-        self.shader_program.use_program();
+        if let Some(recorder) = self.recorder.as_mut() {
+            recorder.record(delta_time, &self.camera);
+        }
 
-        let model = Mat4::identity();
-        let view = self.camera.get_view_matrix();
-        let projection = perspective(45.0 * PI / 180.0, WIDTH as f32 / HEIGHT as f32, 0.1, 100.0);
+        let lights = self.frame_lights();
+        let frame = Frame {
+            camera: &self.camera,
+            lights: &lights,
+            wireframe: self.wireframe,
+            unix_seconds: self.clock.unix_seconds,
+            elapsed: self.elapsed,
+        };
+        self.backend.update(&frame, delta_time, simulated_seconds);
+    }
 
-        self.shader_program.set_mat4("model", &model);
-        self.shader_program.set_mat4("view", &view);
-        self.shader_program.set_mat4("projection", &projection);
-        self.shader_program
-            .set_vec3("viewPos", &self.camera.position);
+    pub fn render(&mut self) -> RenderStats {
+        let lights = self.frame_lights();
+        let frame = Frame {
+            camera: &self.camera,
+            lights: &lights,
+            wireframe: self.wireframe,
+            unix_seconds: self.clock.unix_seconds,
+            elapsed: self.elapsed,
+        };
 
-        self.light.update_shader(&self.shader_program);
+        if self.backend.begin_pass(Pass::Shadow, &frame) {
+            for instance in &self.instances {
+                if instance.object.casts_shadows() {
+                    self.backend.draw(&draw_call(&self.pipelines, instance));
+                }
+            }
+            self.backend.end_pass(&frame);
+        }
 
+        let view = self.camera.get_view_matrix();
+        let fov_y = self.camera.fov * PI / 180.0;
+        let (near, far) = clip_planes(self.camera.position);
+        let culling_projection = perspective(fov_y, self.aspect, near, far);
+        let frustum = Frustum::from_matrix(&(&culling_projection * &view));
+        let mut stats = RenderStats::default();
+        let mut opaque = Vec::new();
+        let mut transparent = Vec::new();
         for instance in self.instances.iter() {
-            instance.draw();
+            let bounds = instance.object.bounding_sphere();
+            if !frustum.intersects_sphere(&bounds) {
+                stats.frustum_culled += 1;
+            } else if self.globe.occludes(self.camera.position, &bounds) {
+                stats.horizon_culled += 1;
+            } else if instance.object.material().is_transparent() {
+                transparent.push(instance);
+            } else {
+                opaque.push(instance);
+            }
+        }
+
+        // Opaque draws grouped by program then material, blended ones back to front.
+        opaque.sort_by_key(|instance| {
+            let material = instance.object.material();
+            (material.shading, Rc::as_ptr(material))
+        });
+        let eye = self.camera.position;
+        let distance =
+            |instance: &&Instance| (instance.object.bounding_sphere().center - eye).length();
+        transparent.sort_by(|a, b| distance(b).total_cmp(&distance(a)));
+
+        if self.backend.begin_pass(Pass::Scene, &frame) {
+            for instance in opaque.iter().chain(transparent.iter()) {
+                self.backend.draw(&draw_call(&self.pipelines, instance));
+            }
+            stats.drawn = opaque.len() + transparent.len();
+            self.backend.end_pass(&frame);
         }
+
+        stats
     }
 
-    pub fn push_instance(&mut self, instance: impl Object + 'static) {
-        self.instances.push(Box::new(instance));
+    // The sun is always the first light.
+    fn frame_lights(&self) -> Vec<Light> {
+        std::iter::once(self.sun)
+            .chain(self.lights.iter().copied())
+            .collect()
+    }
+
+    pub fn has_stale_shaders(&self) -> bool {
+        self.backend.has_stale_shaders()
+    }
+
+    pub fn push_light(&mut self, light: Light) {
+        self.lights.push(light);
+    }
+
+    // Fails when the backend cannot draw the instance's mesh.
+    pub fn push_instance(&mut self, instance: impl Object + 'static) -> Result<(), BackendError> {
+        let mesh = self.backend.create_mesh(instance.mesh())?;
+        self.instances.push(Instance {
+            object: Box::new(instance),
+            mesh,
+        });
+        Ok(())
+    }
+
+    pub fn add_texture(&mut self, image: &ImageData) -> TextureId {
+        self.backend.create_texture(image)
+    }
+
+    // `None` when the file is missing or cannot be decoded.
+    pub fn load_texture(&mut self, path: &str, srgb: bool) -> Option<TextureId> {
+        ImageData::load_optional(Path::new(path), srgb).map(|image| self.add_texture(&image))
+    }
+
+    pub fn read_pixels(&mut self) -> Option<ImageData> {
+        self.backend.read_pixels()
+    }
+
+    // Ignored while the window is minimised to nothing.
+    pub fn resize(&mut self, width: u32, height: u32) {
+        if width > 0 && height > 0 && self.backend.resize(width, height) {
+            self.aspect = width as f32 / height as f32;
+        }
     }
 
     pub fn toggle_wireframe(&mut self) {
-        unsafe {
-            let mut polygon_mode: gl::types::GLint = 0;
-            gl::GetIntegerv(gl::POLYGON_MODE, &mut polygon_mode);
-            let new_mode = if polygon_mode == gl::LINE as gl::types::GLint {
-                gl::FILL
-            } else {
-                gl::LINE
-            };
-            gl::PolygonMode(gl::FRONT_AND_BACK, new_mode);
+        self.wireframe = !self.wireframe;
+    }
+
+    pub fn fly_to(
+        &mut self,
+        lat: f32,
+        lon: f32,
+        height: f32,
+        heading: f32,
+        pitch: f32,
+        duration: f32,
+    ) {
+        self.camera
+            .fly_to(lat, lon, height, heading, pitch, duration);
+    }
+
+    pub fn view_state(&self) -> ViewState {
+        self.camera.view_state()
+    }
+
+    pub fn restore_view(&mut self, view: &ViewState) {
+        self.camera.set_view_state(view);
+    }
+
+    pub fn recall_view(&mut self, view: &ViewState) {
+        self.camera.fly_to_view(view, 1.5);
+    }
+
+    pub fn toggle_camera_mode(&mut self) {
+        self.camera.toggle_mode();
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recorder.is_some()
+    }
+
+    pub fn start_recording(&mut self) {
+        self.recorder = Some(Recorder::new());
+    }
+
+    pub fn stop_recording(&mut self, path: &Path) -> io::Result<()> {
+        match self.recorder.take() {
+            Some(recorder) => recorder.save(path),
+            None => Ok(()),
         }
     }
 
+    pub fn is_playing(&self) -> bool {
+        self.playback.is_some()
+    }
+
+    pub fn start_playback(&mut self, path: &Path) -> io::Result<()> {
+        self.camera.cancel_flight();
+        self.playback = Some(Playback::load(path)?);
+        Ok(())
+    }
+
+    pub fn stop_playback(&mut self) {
+        self.playback = None;
+    }
+
+    pub fn toggle_effect(&mut self, effect: Effect) {
+        self.backend.toggle_effect(effect);
+    }
+
+    pub fn cycle_tone_mapper(&mut self) {
+        self.backend.cycle_tone_mapper();
+    }
+
+    pub fn adjust_exposure(&mut self, stops: f32) {
+        self.backend.adjust_exposure(stops);
+    }
+
+    pub fn cycle_flight_easing(&mut self) {
+        self.camera.flight_easing = self.camera.flight_easing.next();
+    }
+
     pub fn process_keyboard(&mut self, direction: CameraMovement, pressed: bool) {
         self.camera.process_keyboard(direction, pressed);
     }
@@ -86,3 +305,21 @@ impl Scene {
         self.camera.process_mouse_movement(x_offset, y_offset, true);
     }
 }
+
+fn draw_call<'a>(pipelines: &[(PipelineDesc, PipelineId)], instance: &'a Instance) -> DrawCall<'a> {
+    let material = instance.object.material();
+    let desc = PipelineDesc {
+        shading: material.shading,
+        blended: material.is_transparent(),
+    };
+    let (_, pipeline) = pipelines
+        .iter()
+        .find(|(created, _)| *created == desc)
+        .expect("Scene::new creates a pipeline for every shading");
+    DrawCall {
+        pipeline: *pipeline,
+        mesh: instance.mesh,
+        material,
+        receives_shadows: instance.object.receives_shadows(),
+    }
+}
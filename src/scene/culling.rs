@@ -0,0 +1,141 @@
+use crate::algebra::mat4::Mat4;
+use crate::algebra::vec3::Vec3;
+use crate::objects::objects::BoundingSphere;
+
+#[derive(Clone, Copy, Default)]
+pub struct RenderStats {
+    pub drawn: usize,
+    pub frustum_culled: usize,
+    pub horizon_culled: usize,
+}
+
+struct Plane {
+    normal: Vec3,
+    distance: f32,
+}
+
+pub struct Frustum {
+    planes: [Plane; 6],
+}
+
+impl Frustum {
+    // Gribb & Hartmann plane extraction from a view-projection matrix.
+    pub fn from_matrix(view_projection: &Mat4) -> Self {
+        let r = [
+            view_projection.row(0),
+            view_projection.row(1),
+            view_projection.row(2),
+            view_projection.row(3),
+        ];
+        let plane = |sign: f32, row: usize| {
+            let normal = Vec3::new(
+                r[3][0] + sign * r[row][0],
+                r[3][1] + sign * r[row][1],
+                r[3][2] + sign * r[row][2],
+            );
+            let length = normal.length();
+            Plane {
+                normal: normal * (1.0 / length),
+                distance: (r[3][3] + sign * r[row][3]) / length,
+            }
+        };
+
+        Frustum {
+            planes: [
+                plane(1.0, 0),
+                plane(-1.0, 0),
+                plane(1.0, 1),
+                plane(-1.0, 1),
+                plane(1.0, 2),
+                plane(-1.0, 2),
+            ],
+        }
+    }
+
+    pub fn intersects_sphere(&self, sphere: &BoundingSphere) -> bool {
+        self.planes
+            .iter()
+            .all(|p| p.normal.dot(&sphere.center) + p.distance >= -sphere.radius)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scene::camera::{look_at, perspective};
+
+    const EYE: Vec3 = Vec3 {
+        x: 3.0,
+        y: 2.0,
+        z: 10.0,
+    };
+
+    // 90 degrees across both ways from near 1 to far 100, looking down -z from EYE: the side
+    // planes are |x| = -z and |y| = -z in view space.
+    fn frustum() -> Frustum {
+        let projection = perspective(90f32.to_radians(), 1.0, 1.0, 100.0);
+        let view = look_at(
+            EYE,
+            EYE + Vec3::new(0.0, 0.0, -1.0),
+            Vec3::new(0.0, 1.0, 0.0),
+        );
+        Frustum::from_matrix(&(&projection * &view))
+    }
+
+    // A unit sphere at `offset` from the eye.
+    fn sphere(x: f32, y: f32, z: f32) -> BoundingSphere {
+        BoundingSphere {
+            center: EYE + Vec3::new(x, y, z),
+            radius: 1.0,
+        }
+    }
+
+    #[test]
+    fn spheres_inside_are_kept() {
+        let frustum = frustum();
+        assert!(frustum.intersects_sphere(&sphere(0.0, 0.0, -10.0)));
+        assert!(frustum.intersects_sphere(&sphere(5.0, -5.0, -50.0)));
+    }
+
+    #[test]
+    fn spheres_outside_each_plane_are_culled() {
+        let frustum = frustum();
+        for (x, y, z) in [
+            (-12.0, 0.0, -10.0),
+            (12.0, 0.0, -10.0),
+            (0.0, -12.0, -10.0),
+            (0.0, 12.0, -10.0),
+            (0.0, 0.0, 1.5),
+            (0.0, 0.0, -101.5),
+        ] {
+            assert!(
+                !frustum.intersects_sphere(&sphere(x, y, z)),
+                "({}, {}, {})",
+                x,
+                y,
+                z
+            );
+        }
+    }
+
+    #[test]
+    fn spheres_straddling_each_plane_are_kept() {
+        let frustum = frustum();
+        for (x, y, z) in [
+            (-10.5, 0.0, -10.0),
+            (10.5, 0.0, -10.0),
+            (0.0, -10.5, -10.0),
+            (0.0, 10.5, -10.0),
+            (0.0, 0.0, -0.5),
+            (0.0, 0.0, -100.5),
+        ] {
+            assert!(
+                frustum.intersects_sphere(&sphere(x, y, z)),
+                "({}, {}, {})",
+                x,
+                y,
+                z
+            );
+        }
+    }
+}
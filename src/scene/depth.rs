@@ -0,0 +1,174 @@
+use crate::algebra::mat4::Mat4;
+use crate::algebra::vec3::Vec3;
+use crate::core::constants::{FAR_PLANE, GLOBE_RADIUS, METERS_PER_UNIT, MIN_NEAR_PLANE};
+use crate::scene::camera::perspective;
+use crate::shaders::program::ShaderProgram;
+
+use std::ffi::CStr;
+
+// Logarithmic depth is taken over metres, log2(scale * w + 1), so the curve keeps its
+// resolution near the camera instead of spending it below one scene unit.
+pub const LOG_DEPTH_SCALE: f32 = METERS_PER_UNIT;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum DepthMode {
+    // glClipControl, a 32-bit float depth buffer and an infinite far plane.
+    ReversedZ,
+    // Logarithmic depth written from the fragment shader, for contexts without glClipControl.
+    Logarithmic,
+}
+
+impl DepthMode {
+    // A loaded glClipControl pointer alone does not mean the context supports it, so this
+    // checks for OpenGL 4.5 or GL_ARB_clip_control as well.
+    pub fn detect() -> Self {
+        if gl::ClipControl::is_loaded() && supports_clip_control() {
+            DepthMode::ReversedZ
+        } else {
+            DepthMode::Logarithmic
+        }
+    }
+
+    pub fn clear_depth(&self) -> f64 {
+        match self {
+            DepthMode::ReversedZ => 0.0,
+            DepthMode::Logarithmic => 1.0,
+        }
+    }
+
+    pub fn apply(&self) {
+        unsafe {
+            match self {
+                DepthMode::ReversedZ => {
+                    gl::ClipControl(gl::LOWER_LEFT, gl::ZERO_TO_ONE);
+                    gl::DepthFunc(gl::GREATER);
+                }
+                DepthMode::Logarithmic => {
+                    gl::DepthFunc(gl::LESS);
+                }
+            }
+            gl::ClearDepth(self.clear_depth());
+        }
+    }
+
+    pub fn update_shader(&self, shader: &ShaderProgram, far: f32) {
+        shader.set_bool("logDepth", *self == DepthMode::Logarithmic);
+        shader.set_float("logDepthCoef", log_depth_coefficient(far));
+        shader.set_float("logDepthScale", LOG_DEPTH_SCALE);
+    }
+
+    pub fn projection(&self, fov_y: f32, aspect: f32, near: f32, far: f32) -> Mat4 {
+        match self {
+            DepthMode::ReversedZ => reversed_infinite_perspective(fov_y, aspect, near),
+            DepthMode::Logarithmic => perspective(fov_y, aspect, near, far),
+        }
+    }
+}
+
+fn supports_clip_control() -> bool {
+    let (mut major, mut minor, mut count) = (0, 0, 0);
+    unsafe {
+        gl::GetIntegerv(gl::MAJOR_VERSION, &mut major);
+        gl::GetIntegerv(gl::MINOR_VERSION, &mut minor);
+        if (major, minor) >= (4, 5) {
+            return true;
+        }
+
+        gl::GetIntegerv(gl::NUM_EXTENSIONS, &mut count);
+        (0..count.max(0) as u32).any(|index| {
+            let name = gl::GetStringi(gl::EXTENSIONS, index);
+            !name.is_null() && CStr::from_ptr(name as *const _).to_bytes() == b"GL_ARB_clip_control"
+        })
+    }
+}
+
+// Near plane follows the altitude so it stays as far out as possible without clipping the ground.
+pub fn clip_planes(eye: Vec3) -> (f32, f32) {
+    let altitude = (eye.length() - GLOBE_RADIUS).max(0.0);
+    let near = (altitude * 0.5).clamp(MIN_NEAR_PLANE, 1.0);
+    (near, FAR_PLANE)
+}
+
+// Maps the near plane to depth 1 and infinity to depth 0, for a [0, 1] clip range.
+pub fn reversed_infinite_perspective(fov_y: f32, aspect: f32, near: f32) -> Mat4 {
+    let f = 1.0 / (fov_y / 2.0).tan();
+    Mat4::new([
+        [f / aspect, 0.0, 0.0, 0.0],
+        [0.0, f, 0.0, 0.0],
+        [0.0, 0.0, 0.0, -1.0],
+        [0.0, 0.0, near, 0.0],
+    ])
+}
+
+// Maps log2(scale * far + 1) to a window depth of 1.
+pub fn log_depth_coefficient(far: f32) -> f32 {
+    2.0 / (LOG_DEPTH_SCALE * far + 1.0).log2()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Window depth of a point `distance` in front of the camera, for a [0, 1] clip range.
+    fn window_depth(projection: &Mat4, distance: f32) -> f32 {
+        let point = [0.0, 0.0, -distance, 1.0];
+        let dot = |row: [f32; 4]| (0..4).map(|i| row[i] * point[i]).sum::<f32>();
+        dot(projection.row(2)) / dot(projection.row(3))
+    }
+
+    // What log_depth.glsl writes for a fragment at view depth `w`.
+    fn log_depth(w: f32, far: f32) -> f32 {
+        (LOG_DEPTH_SCALE * w + 1.0).log2() * log_depth_coefficient(far) * 0.5
+    }
+
+    #[test]
+    fn reversed_z_maps_near_to_one_and_infinity_to_zero() {
+        let near = 0.01;
+        let projection = reversed_infinite_perspective(60f32.to_radians(), 1.5, near);
+        assert!((window_depth(&projection, near) - 1.0).abs() < 1e-6);
+        assert!(window_depth(&projection, 1e30) < 1e-6);
+
+        let mut previous = 1.0;
+        for distance in [0.1, 1.0, 10.0, FAR_PLANE] {
+            let depth = window_depth(&projection, distance);
+            assert!(depth > 0.0 && depth < previous, "{} at {}", depth, distance);
+            previous = depth;
+        }
+    }
+
+    #[test]
+    fn clip_planes_stay_positive_and_ordered() {
+        let mut previous_near = 0.0;
+        // From one metre below the ground out past geostationary orbit.
+        for altitude in [-1.0, 0.0, 1.0, 100.0, 10_000.0, 400_000.0, 36_000_000.0] {
+            let eye = Vec3::new(0.0, 0.0, GLOBE_RADIUS + altitude / METERS_PER_UNIT);
+            let (near, far) = clip_planes(eye);
+            assert!(
+                near > 0.0 && near < far,
+                "{} {} at {} m",
+                near,
+                far,
+                altitude
+            );
+            assert!(near >= previous_near, "near shrinks at {} m", altitude);
+            previous_near = near;
+        }
+        assert_eq!(
+            clip_planes(Vec3::new(0.0, GLOBE_RADIUS, 0.0)).0,
+            MIN_NEAR_PLANE
+        );
+    }
+
+    #[test]
+    fn log_depth_spans_zero_to_one_over_the_far_plane() {
+        assert_eq!(log_depth(0.0, FAR_PLANE), 0.0);
+        assert!((log_depth(FAR_PLANE, FAR_PLANE) - 1.0).abs() < 1e-5);
+
+        let mut previous = 0.0;
+        for w in [MIN_NEAR_PLANE, 1e-4, 0.01, 1.0, 10.0] {
+            let depth = log_depth(w, FAR_PLANE);
+            assert!(depth > previous && depth < 1.0, "{} at {}", depth, w);
+            previous = depth;
+        }
+    }
+}
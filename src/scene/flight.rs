@@ -0,0 +1,298 @@
+use crate::algebra::vec3::Vec3;
+use crate::core::constants::GLOBE_RADIUS;
+use crate::scene::geo::{local_frame, slerp};
+
+use std::f32::consts::PI;
+
+const MIN_ALTITUDE: f32 = 1e-4;
+// Curvature of the zoom/pan path, the value recommended by van Wijk and Nuij.
+const RHO: f32 = 1.42;
+
+#[derive(Clone, Copy)]
+pub enum Easing {
+    Linear,
+    EaseInOutSine,
+    EaseInOutCubic,
+}
+
+impl Easing {
+    pub fn apply(&self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseInOutSine => 0.5 - 0.5 * (PI * t).cos(),
+            Easing::EaseInOutCubic => {
+                if t < 0.5 {
+                    4.0 * t * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+                }
+            }
+        }
+    }
+
+    pub fn next(&self) -> Easing {
+        match self {
+            Easing::Linear => Easing::EaseInOutSine,
+            Easing::EaseInOutSine => Easing::EaseInOutCubic,
+            Easing::EaseInOutCubic => Easing::Linear,
+        }
+    }
+}
+
+// Smooth zoom and pan path from "Smooth and efficient zooming and panning"
+// (van Wijk & Nuij, 2003). `u` is the distance travelled over the ground and
+// `w` the altitude, so the camera rises as the distance to cover grows.
+struct ZoomPath {
+    w0: f32,
+    w1: f32,
+    u1: f32,
+    r0: f32,
+    length: f32,
+}
+
+impl ZoomPath {
+    fn new(w0: f32, w1: f32, u1: f32) -> Self {
+        if u1 < 1e-6 {
+            let length = (w1 / w0).ln().abs() / RHO;
+            return ZoomPath {
+                w0,
+                w1,
+                u1,
+                r0: 0.0,
+                length,
+            };
+        }
+
+        let rho2 = RHO * RHO;
+        let b0 = (w1 * w1 - w0 * w0 + rho2 * rho2 * u1 * u1) / (2.0 * w0 * rho2 * u1);
+        let b1 = (w1 * w1 - w0 * w0 - rho2 * rho2 * u1 * u1) / (2.0 * w1 * rho2 * u1);
+        let r0 = -b0.asinh();
+        let r1 = -b1.asinh();
+
+        ZoomPath {
+            w0,
+            w1,
+            u1,
+            r0,
+            length: (r1 - r0) / RHO,
+        }
+    }
+
+    // Returns the fraction of the ground distance covered and the altitude at `t` in [0, 1].
+    fn at(&self, t: f32) -> (f32, f32) {
+        if self.length < 1e-6 {
+            return (t, self.w0 + (self.w1 - self.w0) * t);
+        }
+
+        let s = t * self.length;
+        if self.u1 < 1e-6 {
+            let k = if self.w1 < self.w0 { -1.0 } else { 1.0 };
+            return (t, self.w0 * (k * RHO * s).exp());
+        }
+
+        let (cosh_r0, sinh_r0) = (self.r0.cosh(), self.r0.sinh());
+        let u = self.w0 / (RHO * RHO) * (cosh_r0 * (RHO * s + self.r0).tanh() - sinh_r0);
+        let w = self.w0 * cosh_r0 / (RHO * s + self.r0).cosh();
+        ((u / self.u1).clamp(0.0, 1.0), w)
+    }
+}
+
+pub struct Flight {
+    start_dir: Vec3,
+    end_dir: Vec3,
+    start_heading: f32,
+    heading_delta: f32,
+    start_pitch: f32,
+    pitch_delta: f32,
+    path: ZoomPath,
+    duration: f32,
+    elapsed: f32,
+    easing: Easing,
+}
+
+impl Flight {
+    pub fn new(
+        from: Vec3,
+        front: Vec3,
+        to: Vec3,
+        heading: f32,
+        pitch: f32,
+        duration: f32,
+        easing: Easing,
+    ) -> Self {
+        let (start_heading, start_pitch) = local_frame(from).heading_pitch(front);
+        let heading_delta = (heading - start_heading + 540.0).rem_euclid(360.0) - 180.0;
+
+        let start_dir = from.normalize();
+        let end_dir = to.normalize();
+        let angle = start_dir.dot(&end_dir).clamp(-1.0, 1.0).acos();
+
+        let w0 = (from.length() - GLOBE_RADIUS).max(MIN_ALTITUDE);
+        let w1 = (to.length() - GLOBE_RADIUS).max(MIN_ALTITUDE);
+
+        Flight {
+            start_dir,
+            end_dir,
+            start_heading,
+            heading_delta,
+            start_pitch,
+            pitch_delta: pitch - start_pitch,
+            path: ZoomPath::new(w0, w1, angle * GLOBE_RADIUS),
+            duration: duration.max(0.0),
+            elapsed: 0.0,
+            easing,
+        }
+    }
+
+    // Advances the flight and returns the new camera position and front vector.
+    pub fn step(&mut self, delta_time: f32) -> (Vec3, Vec3) {
+        self.elapsed = (self.elapsed + delta_time).min(self.duration);
+        let t = if self.duration > 0.0 {
+            self.easing.apply(self.elapsed / self.duration)
+        } else {
+            1.0
+        };
+
+        let (fraction, altitude) = self.path.at(t);
+        let position = slerp(self.start_dir, self.end_dir, fraction) * (GLOBE_RADIUS + altitude);
+
+        let heading = self.start_heading + self.heading_delta * t;
+        let pitch = self.start_pitch + self.pitch_delta * t;
+        let front = local_frame(position).direction(heading, pitch);
+
+        (position, front)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scene::geo::geodetic_to_cartesian;
+
+    const DT: f32 = 1.0 / 60.0;
+    const EASINGS: [Easing; 3] = [
+        Easing::Linear,
+        Easing::EaseInOutSine,
+        Easing::EaseInOutCubic,
+    ];
+
+    fn assert_near(a: Vec3, b: Vec3, tolerance: f32) {
+        assert!(
+            (a - b).length() < tolerance,
+            "({}, {}, {}) != ({}, {}, {})",
+            a.x,
+            a.y,
+            a.z,
+            b.x,
+            b.y,
+            b.z
+        );
+    }
+
+    // Steps with a fixed time step until the flight reports it is done.
+    fn fly(flight: &mut Flight) -> Vec<(Vec3, Vec3)> {
+        let mut poses = vec![flight.step(0.0)];
+        while !flight.is_finished() {
+            poses.push(flight.step(DT));
+        }
+        poses
+    }
+
+    #[test]
+    fn easings_are_monotonic_from_zero_to_one() {
+        for easing in EASINGS {
+            assert_eq!(easing.apply(0.0), 0.0);
+            assert!((easing.apply(1.0) - 1.0).abs() < 1e-6);
+            let values: Vec<f32> = (0..=1000)
+                .map(|i| easing.apply(i as f32 / 1000.0))
+                .collect();
+            assert!(values.windows(2).all(|pair| pair[1] >= pair[0]));
+        }
+    }
+
+    #[test]
+    fn zoom_path_joins_the_two_altitudes() {
+        // A pan across the globe, a pure zoom and no move at all.
+        for (w0, w1, u1) in [(0.5, 0.01, 2.0), (2.0, 0.001, 0.0), (0.3, 0.3, 0.0)] {
+            let path = ZoomPath::new(w0, w1, u1);
+            let (u_start, w_start) = path.at(0.0);
+            let (u_end, w_end) = path.at(1.0);
+            assert!(u_start.abs() < 1e-5);
+            assert!((u_end - 1.0).abs() < 1e-4);
+            assert!((w_start - w0).abs() < 1e-4 * w0);
+            assert!((w_end - w1).abs() < 1e-3 * w1.max(1e-3));
+
+            let fractions: Vec<f32> = (0..=100).map(|i| path.at(i as f32 / 100.0).0).collect();
+            assert!(fractions.windows(2).all(|pair| pair[1] >= pair[0] - 1e-6));
+        }
+    }
+
+    #[test]
+    fn flight_starts_and_ends_at_the_requested_views() {
+        let from = Vec3::new(0.0, 0.0, 3.0);
+        let front = Vec3::new(0.0, 0.0, -1.0);
+        let to = geodetic_to_cartesian(48.8566, 2.3522, 0.3);
+        let (heading, pitch) = (30.0, -40.0);
+
+        for easing in EASINGS {
+            let mut flight = Flight::new(from, front, to, heading, pitch, 4.0, easing);
+            let poses = fly(&mut flight);
+
+            let (first_position, first_front) = poses[0];
+            assert_near(first_position, from, 1e-4);
+            assert_near(first_front, front, 1e-4);
+
+            let (last_position, last_front) = *poses.last().unwrap();
+            assert_near(last_position, to, 1e-4);
+            let (last_heading, last_pitch) = local_frame(last_position).heading_pitch(last_front);
+            assert!((last_heading - heading).abs() < 1e-2);
+            assert!((last_pitch - pitch).abs() < 1e-2);
+            // The elapsed time accumulates in f32, allow one step of rounding.
+            assert!(poses.len() <= 2 + (4.0 / DT).round() as usize);
+        }
+    }
+
+    #[test]
+    fn flight_progress_along_the_ground_is_monotonic() {
+        let from = geodetic_to_cartesian(0.0, 0.0, 1.0);
+        let to = geodetic_to_cartesian(35.6762, 139.6503, 0.05);
+        let mut flight = Flight::new(
+            from,
+            Vec3::new(0.0, 0.0, -1.0),
+            to,
+            0.0,
+            -60.0,
+            3.0,
+            Easing::EaseInOutCubic,
+        );
+
+        let start_dir = from.normalize();
+        let angles: Vec<f32> = fly(&mut flight)
+            .iter()
+            .map(|(position, _)| start_dir.dot(&position.normalize()).clamp(-1.0, 1.0).acos())
+            .collect();
+        assert!(angles.windows(2).all(|pair| pair[1] >= pair[0] - 1e-4));
+    }
+
+    #[test]
+    fn zero_duration_lands_immediately() {
+        let to = geodetic_to_cartesian(-33.8688, 151.2093, 0.2);
+        let mut flight = Flight::new(
+            Vec3::new(0.0, 0.0, 3.0),
+            Vec3::new(0.0, 0.0, -1.0),
+            to,
+            0.0,
+            -30.0,
+            0.0,
+            Easing::Linear,
+        );
+        let (position, _) = flight.step(0.0);
+        assert_near(position, to, 1e-4);
+        assert!(flight.is_finished());
+    }
+}
@@ -0,0 +1,166 @@
+use crate::algebra::vec3::Vec3;
+use crate::core::constants::GLOBE_RADIUS;
+use crate::objects::objects::BoundingSphere;
+
+pub struct LocalFrame {
+    pub east: Vec3,
+    pub north: Vec3,
+    pub up: Vec3,
+}
+
+// Latitude and longitude are in degrees, height is above the globe surface.
+// Longitude 0 faces +z and the north pole is +y, matching the default camera.
+pub fn geodetic_to_cartesian(lat: f32, lon: f32, height: f32) -> Vec3 {
+    let (lat, lon) = (lat.to_radians(), lon.to_radians());
+    let r = GLOBE_RADIUS + height;
+    Vec3::new(
+        r * lat.cos() * lon.sin(),
+        r * lat.sin(),
+        r * lat.cos() * lon.cos(),
+    )
+}
+
+pub fn local_frame(position: Vec3) -> LocalFrame {
+    let up = position.normalize();
+    let east = Vec3::new(0.0, 1.0, 0.0).cross(&up);
+    let east = if east.length() < 1e-6 {
+        Vec3::new(1.0, 0.0, 0.0)
+    } else {
+        east.normalize()
+    };
+    let north = up.cross(&east);
+
+    LocalFrame { east, north, up }
+}
+
+impl LocalFrame {
+    // Heading is clockwise from north, pitch is positive above the horizon, both in degrees.
+    pub fn direction(&self, heading: f32, pitch: f32) -> Vec3 {
+        let (heading, pitch) = (heading.to_radians(), pitch.to_radians());
+        let horizontal = self.north * heading.cos() + self.east * heading.sin();
+        (horizontal * pitch.cos() + self.up * pitch.sin()).normalize()
+    }
+
+    pub fn heading_pitch(&self, direction: Vec3) -> (f32, f32) {
+        let direction = direction.normalize();
+        let pitch = direction.dot(&self.up).clamp(-1.0, 1.0).asin();
+        let heading = direction.dot(&self.east).atan2(direction.dot(&self.north));
+        (heading.to_degrees(), pitch.to_degrees())
+    }
+}
+
+pub struct Ellipsoid {
+    pub radii: Vec3,
+}
+
+impl Ellipsoid {
+    pub fn sphere(radius: f32) -> Self {
+        Ellipsoid {
+            radii: Vec3::new(radius, radius, radius),
+        }
+    }
+
+    // Horizon culling: whether `sphere` is entirely hidden behind the ellipsoid when seen
+    // from `eye`. Works in the scaled space where the ellipsoid becomes the unit sphere.
+    pub fn occludes(&self, eye: Vec3, sphere: &BoundingSphere) -> bool {
+        let scale = Vec3::new(1.0 / self.radii.x, 1.0 / self.radii.y, 1.0 / self.radii.z);
+        let eye = eye * scale;
+        let center = sphere.center * scale;
+        let radius = sphere.radius / self.radii.x.min(self.radii.y).min(self.radii.z);
+
+        let eye_distance = eye.length();
+        if eye_distance <= 1.0 {
+            return false;
+        }
+        let eye_dir = eye * (1.0 / eye_distance);
+
+        // Must lie entirely behind the plane of the horizon circle...
+        if center.dot(&eye_dir) + radius >= 1.0 / eye_distance {
+            return false;
+        }
+
+        // ...and entirely inside the cone of the ellipsoid silhouette.
+        let to_center = center - eye;
+        let distance = to_center.length();
+        if distance <= radius {
+            return false;
+        }
+        let angle = (to_center.dot(&-eye_dir) / distance)
+            .clamp(-1.0, 1.0)
+            .acos();
+        angle + (radius / distance).asin() < (1.0 / eye_distance).asin()
+    }
+}
+
+pub fn slerp(from: Vec3, to: Vec3, t: f32) -> Vec3 {
+    let (from, to) = (from.normalize(), to.normalize());
+    let angle = from.dot(&to).clamp(-1.0, 1.0).acos();
+    if angle < 1e-4 {
+        return from.lerp(&to, t).normalize();
+    }
+
+    if angle.sin() < 1e-4 {
+        // Antipodal points: every great circle works, route through a perpendicular point.
+        let axis = from.cross(&Vec3::new(0.0, 1.0, 0.0));
+        let axis = if axis.length() < 1e-6 {
+            Vec3::new(1.0, 0.0, 0.0)
+        } else {
+            axis.normalize()
+        };
+        let mid = axis.cross(&from).normalize();
+        return if t < 0.5 {
+            slerp(from, mid, t * 2.0)
+        } else {
+            slerp(mid, to, t * 2.0 - 1.0)
+        };
+    }
+
+    (from * ((1.0 - t) * angle).sin() + to * (t * angle).sin()) * (1.0 / angle.sin())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Three radii out from a unit globe, the horizon circle sits at z = 1/3 and its cone
+    // opens asin(1/3) around the line to the centre.
+    const EYE: Vec3 = Vec3 {
+        x: 0.0,
+        y: 0.0,
+        z: 3.0,
+    };
+
+    fn sphere(x: f32, y: f32, z: f32, radius: f32) -> BoundingSphere {
+        BoundingSphere {
+            center: Vec3::new(x, y, z),
+            radius,
+        }
+    }
+
+    #[test]
+    fn spheres_behind_the_horizon_are_occluded() {
+        let globe = Ellipsoid::sphere(1.0);
+        assert!(globe.occludes(EYE, &sphere(0.0, 0.0, -1.5, 0.1)));
+        assert!(globe.occludes(EYE, &sphere(0.0, 1.3, -1.0, 0.01)));
+    }
+
+    #[test]
+    fn spheres_above_the_horizon_are_visible() {
+        let globe = Ellipsoid::sphere(1.0);
+        assert!(!globe.occludes(EYE, &sphere(0.0, 1.5, -1.0, 0.01)));
+        assert!(!globe.occludes(EYE, &sphere(0.0, 0.0, 1.2, 0.1)));
+        // Centred behind the globe but large enough to poke out past the silhouette.
+        assert!(!globe.occludes(EYE, &sphere(0.0, 0.0, -1.5, 2.0)));
+    }
+
+    #[test]
+    fn an_eye_inside_the_ellipsoid_never_occludes() {
+        let globe = Ellipsoid::sphere(1.0);
+        assert!(!globe.occludes(Vec3::new(0.0, 0.0, 0.5), &sphere(0.0, 0.0, -1.5, 0.1)));
+        // Outside a unit sphere but inside this one's stretched z axis.
+        let stretched = Ellipsoid {
+            radii: Vec3::new(1.0, 1.0, 4.0),
+        };
+        assert!(!stretched.occludes(EYE, &sphere(0.0, 0.0, -5.0, 0.1)));
+    }
+}
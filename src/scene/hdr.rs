@@ -0,0 +1,298 @@
+use crate::render::buffer::Buffer;
+use crate::render::fence::Fence;
+use crate::render::framebuffer::{bind_default, Framebuffer};
+use crate::render::fullscreen::FullscreenTriangle;
+use crate::shaders::error::ShaderError;
+use crate::shaders::program::ShaderProgram;
+use crate::textures::texture::Texture;
+
+// The default framebuffer only offers fixed-point depth that cannot be sampled and clamps
+// colour to [0, 1], so the scene renders into a half-float colour texture with a float
+// depth texture. Later passes read the depth back, e.g. for aerial perspective, and the
+// tone mapping pass resolves the colour to the window.
+pub struct HdrTarget {
+    pub width: u32,
+    pub height: u32,
+    framebuffer: Framebuffer,
+    // Same colour texture without the depth attachment, for passes that sample the depth.
+    color_framebuffer: Framebuffer,
+    color: Texture,
+    depth: Texture,
+}
+
+impl HdrTarget {
+    pub fn new(width: u32, height: u32) -> Self {
+        let color = Texture::new(gl::TEXTURE_2D);
+        color.image_2d(gl::RGBA16F, width, height, gl::RGBA, gl::FLOAT, None);
+        color.set_filter(gl::LINEAR, gl::LINEAR);
+        color.set_wrap(gl::CLAMP_TO_EDGE);
+
+        let depth = Texture::new(gl::TEXTURE_2D);
+        depth.image_2d(
+            gl::DEPTH_COMPONENT32F,
+            width,
+            height,
+            gl::DEPTH_COMPONENT,
+            gl::FLOAT,
+            None,
+        );
+        depth.set_filter(gl::NEAREST, gl::NEAREST);
+        depth.set_wrap(gl::CLAMP_TO_EDGE);
+
+        let framebuffer = Framebuffer::new();
+        framebuffer.attach(gl::COLOR_ATTACHMENT0, &color);
+        framebuffer.attach(gl::DEPTH_ATTACHMENT, &depth);
+
+        let color_framebuffer = Framebuffer::new();
+        color_framebuffer.attach(gl::COLOR_ATTACHMENT0, &color);
+        bind_default();
+
+        HdrTarget {
+            width,
+            height,
+            framebuffer,
+            color_framebuffer,
+            color,
+            depth,
+        }
+    }
+
+    pub fn bind(&self) {
+        self.framebuffer.bind();
+        self.set_viewport();
+    }
+
+    pub fn bind_color_only(&self) {
+        self.color_framebuffer.bind();
+        self.set_viewport();
+    }
+
+    fn set_viewport(&self) {
+        unsafe {
+            gl::Viewport(0, 0, self.width as i32, self.height as i32);
+        }
+    }
+
+    pub fn bind_color_texture(&self, unit: u32) {
+        self.color.bind(unit);
+    }
+
+    pub fn bind_depth_texture(&self, unit: u32) {
+        self.depth.bind(unit);
+    }
+}
+
+// Side of the square the frame is metered at, a power of two so its mips end at 1x1.
+const METER_SIZE: u32 = 256;
+
+// Log-average luminance of the frame, so a small bright sun does not dominate the reading.
+// Black pixels, e.g. empty space, are left out rather than dragging the average to zero.
+// The log luminance and a coverage weight are drawn at METER_SIZE and averaged down the
+// mips, and the 1x1 mip is read back through a pixel pack buffer once its fence signals, a frame or two later,
+// instead of stalling the CPU until the GPU catches up.
+struct LuminanceMeter {
+    framebuffer: Framebuffer,
+    log_luminance: Texture,
+    pixel_buffer: Buffer,
+    // Set while a reading is in flight, no new one starts until it has been collected.
+    pending: Option<Fence>,
+    shader_program: ShaderProgram,
+    triangle: FullscreenTriangle,
+}
+
+impl LuminanceMeter {
+    fn new() -> Result<Self, ShaderError> {
+        let log_luminance = Texture::new(gl::TEXTURE_2D);
+        log_luminance.image_2d(gl::RG16F, METER_SIZE, METER_SIZE, gl::RG, gl::FLOAT, None);
+        log_luminance.set_filter(gl::LINEAR_MIPMAP_LINEAR, gl::LINEAR);
+        log_luminance.set_wrap(gl::CLAMP_TO_EDGE);
+        log_luminance.generate_mipmaps();
+
+        let framebuffer = Framebuffer::new();
+        framebuffer.attach(gl::COLOR_ATTACHMENT0, &log_luminance);
+        bind_default();
+
+        let pixel_buffer = Buffer::new(gl::PIXEL_PACK_BUFFER);
+        pixel_buffer.allocate(2 * size_of::<f32>(), gl::STREAM_READ);
+        pixel_buffer.unbind();
+
+        Ok(LuminanceMeter {
+            framebuffer,
+            log_luminance,
+            pixel_buffer,
+            pending: None,
+            shader_program: ShaderProgram::new("fullscreen.vert", "luminance.frag")?,
+            triangle: FullscreenTriangle::new(),
+        })
+    }
+
+    // Starts metering the frame in `target`, unless the previous reading is still pending.
+    // Leaves the viewport at METER_SIZE.
+    fn measure(&mut self, target: &HdrTarget) {
+        if self.pending.is_some() {
+            return;
+        }
+
+        self.framebuffer.bind();
+        unsafe {
+            gl::Viewport(0, 0, METER_SIZE as i32, METER_SIZE as i32);
+        }
+        let program = &self.shader_program;
+        program.use_program();
+        target.bind_color_texture(0);
+        program.set_sampler("hdrColor", 0);
+        self.triangle.draw();
+
+        let last_level = METER_SIZE.ilog2() as i32;
+        self.log_luminance.generate_mipmaps();
+        self.log_luminance
+            .pack_level(last_level, gl::RG, &self.pixel_buffer);
+        self.pending = Some(Fence::new());
+    }
+
+    // The reading started by `measure`, once the GPU has produced it. A black frame reads 0.
+    fn collect(&mut self) -> Option<f32> {
+        if !self.pending.as_ref()?.is_signaled() {
+            return None;
+        }
+        self.pending = None;
+
+        let mut texel = [0.0f32; 2];
+        self.pixel_buffer.read(&mut texel);
+        self.pixel_buffer.unbind();
+        let [weighted_log, coverage] = texel;
+        Some(if coverage > 0.0 {
+            (weighted_log / coverage).exp()
+        } else {
+            0.0
+        })
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ToneMapper {
+    Reinhard,
+    Aces,
+    Agx,
+}
+
+impl ToneMapper {
+    pub fn next(self) -> Self {
+        match self {
+            ToneMapper::Reinhard => ToneMapper::Aces,
+            ToneMapper::Aces => ToneMapper::Agx,
+            ToneMapper::Agx => ToneMapper::Reinhard,
+        }
+    }
+
+    // Selects the curve in tonemap.frag.
+    pub fn shader_id(self) -> i32 {
+        match self {
+            ToneMapper::Reinhard => 0,
+            ToneMapper::Aces => 1,
+            ToneMapper::Agx => 2,
+        }
+    }
+}
+
+// In automatic mode `compensation` is added to the metered exposure, in manual mode it is
+// the exposure itself. Both are in stops.
+pub struct Exposure {
+    pub auto: bool,
+    pub compensation: f32,
+    // Middle grey the metered average is mapped to.
+    pub key: f32,
+    // Rate of the exponential adaptation, per second.
+    pub adaptation_speed: f32,
+    pub min_luminance: f32,
+    pub max_luminance: f32,
+    adapted_luminance: f32,
+}
+
+impl Exposure {
+    pub fn new() -> Self {
+        Exposure {
+            auto: true,
+            compensation: 0.0,
+            key: 0.18,
+            adaptation_speed: 1.5,
+            min_luminance: 0.02,
+            max_luminance: 20.0,
+            adapted_luminance: 0.18,
+        }
+    }
+
+    // `average_luminance` is the log-average of the frame, see LuminanceMeter.
+    pub fn adapt(&mut self, average_luminance: f32, delta_time: f32) {
+        let target = average_luminance.clamp(self.min_luminance, self.max_luminance);
+        let blend = 1.0 - (-self.adaptation_speed * delta_time).exp();
+        self.adapted_luminance += (target - self.adapted_luminance) * blend;
+    }
+
+    pub fn toggle_auto(&mut self) {
+        self.auto = !self.auto;
+    }
+
+    pub fn multiplier(&self) -> f32 {
+        let metered = if self.auto {
+            self.key / self.adapted_luminance
+        } else {
+            1.0
+        };
+        metered * self.compensation.exp2()
+    }
+}
+
+// Exposure, tone mapping and sRGB encoding from the HDR target to the bound framebuffer.
+pub struct ToneMapping {
+    pub tone_mapper: ToneMapper,
+    pub exposure: Exposure,
+    meter: LuminanceMeter,
+    // Time since the exposure last adapted, made up when the next reading arrives.
+    unmetered_time: f32,
+    shader_program: ShaderProgram,
+}
+
+impl ToneMapping {
+    pub fn new() -> Result<Self, ShaderError> {
+        Ok(ToneMapping {
+            tone_mapper: ToneMapper::Agx,
+            exposure: Exposure::new(),
+            meter: LuminanceMeter::new()?,
+            unmetered_time: 0.0,
+            shader_program: ShaderProgram::new("fullscreen.vert", "tonemap.frag")?,
+        })
+    }
+
+    pub fn shader_programs(&mut self) -> Vec<&mut ShaderProgram> {
+        vec![&mut self.shader_program, &mut self.meter.shader_program]
+    }
+
+    // Adapts to the latest finished reading, if one arrived since the last update.
+    pub fn update(&mut self, delta_time: f32) {
+        self.unmetered_time += delta_time;
+        if let Some(luminance) = self.meter.collect() {
+            if self.exposure.auto {
+                self.exposure.adapt(luminance, self.unmetered_time);
+            }
+            self.unmetered_time = 0.0;
+        }
+    }
+
+    // Meters the scene in `target` for a later update. Leaves the viewport changed.
+    pub fn measure(&mut self, target: &HdrTarget) {
+        if self.exposure.auto {
+            self.meter.measure(target);
+        }
+    }
+
+    pub fn render(&self, target: &HdrTarget, triangle: &FullscreenTriangle) {
+        let program = &self.shader_program;
+        program.use_program();
+        target.bind_color_texture(0);
+        program.set_sampler("hdrColor", 0);
+        program.set_float("exposure", self.exposure.multiplier());
+        program.set_int("toneMapper", self.tone_mapper.shader_id());
+        triangle.draw();
+    }
+}
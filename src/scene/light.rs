@@ -1,18 +1,132 @@
 use crate::algebra::vec3::Vec3;
 use crate::shaders::program::ShaderProgram;
+use crate::shaders::uniform_buffer::UniformBuffer;
 
+pub const MAX_LIGHTS: usize = 16;
+pub const LIGHTS_BINDING: u32 = 0;
+// std140 size of one light in the `Lights` block: four vec4.
+const LIGHT_FLOATS: usize = 16;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum LightKind {
+    Directional,
+    Point,
+    Spot,
+}
+
+// `direction` points from the surface towards a directional light, and along the
+// cone axis for a spot light. Angles are in degrees, `attenuation` holds the
+// constant, linear and quadratic terms.
+#[derive(Clone, Copy)]
 pub struct Light {
-    position: Vec3,
-    color: Vec3,
+    pub kind: LightKind,
+    pub position: Vec3,
+    pub direction: Vec3,
+    pub color: Vec3,
+    pub intensity: f32,
+    pub attenuation: Vec3,
+    pub inner_angle: f32,
+    pub outer_angle: f32,
 }
 
 impl Light {
-    pub fn new(position: Vec3, color: Vec3) -> Self {
-        Light { position, color }
+    pub fn directional(direction: Vec3, color: Vec3) -> Self {
+        Light {
+            kind: LightKind::Directional,
+            position: Vec3::new(0.0, 0.0, 0.0),
+            direction: direction.normalize(),
+            color,
+            intensity: 1.0,
+            attenuation: Vec3::new(1.0, 0.0, 0.0),
+            inner_angle: 0.0,
+            outer_angle: 0.0,
+        }
+    }
+
+    pub fn point(position: Vec3, color: Vec3, intensity: f32, attenuation: Vec3) -> Self {
+        Light {
+            kind: LightKind::Point,
+            position,
+            direction: Vec3::new(0.0, -1.0, 0.0),
+            color,
+            intensity,
+            attenuation,
+            inner_angle: 0.0,
+            outer_angle: 0.0,
+        }
+    }
+
+    pub fn spot(position: Vec3, direction: Vec3, color: Vec3, intensity: f32) -> Self {
+        Light {
+            kind: LightKind::Spot,
+            position,
+            direction: direction.normalize(),
+            color,
+            intensity,
+            attenuation: Vec3::new(1.0, 0.0, 1.0),
+            inner_angle: 15.0,
+            outer_angle: 25.0,
+        }
+    }
+
+    pub fn set_direction(&mut self, direction: Vec3) {
+        self.direction = direction.normalize();
     }
 
+    // Sun uniforms for the passes that only care about the main directional light.
     pub fn update_shader(&self, shader: &ShaderProgram) {
-        shader.set_vec3("lightPos", &self.position);
-        shader.set_vec3("lightColor", &self.color);
+        shader.set_vec3("lightDir", &self.direction);
+        shader.set_vec3("lightColor", &(self.color * self.intensity));
+    }
+
+    // One element of the `Lights` block.
+    pub fn write_std140(&self, out: &mut Vec<f32>) {
+        let kind = match self.kind {
+            LightKind::Directional => 0.0,
+            LightKind::Point => 1.0,
+            LightKind::Spot => 2.0,
+        };
+        out.extend_from_slice(&[
+            self.position.x,
+            self.position.y,
+            self.position.z,
+            kind,
+            self.direction.x,
+            self.direction.y,
+            self.direction.z,
+            self.inner_angle.to_radians().cos(),
+            self.color.x,
+            self.color.y,
+            self.color.z,
+            self.intensity,
+            self.attenuation.x,
+            self.attenuation.y,
+            self.attenuation.z,
+            self.outer_angle.to_radians().cos(),
+        ]);
+    }
+}
+
+// The `Lights` uniform block shared by every program that shades with the light list.
+pub struct LightBuffer {
+    buffer: UniformBuffer,
+}
+
+impl LightBuffer {
+    pub fn new() -> Self {
+        LightBuffer {
+            buffer: UniformBuffer::new(4 + MAX_LIGHTS * LIGHT_FLOATS, LIGHTS_BINDING),
+        }
+    }
+
+    pub fn upload<'a>(&self, lights: impl Iterator<Item = &'a Light>) {
+        let mut data = vec![0.0; 4];
+        let mut count = 0;
+        for light in lights.take(MAX_LIGHTS) {
+            light.write_std140(&mut data);
+            count += 1;
+        }
+        data[0] = count as f32;
+        self.buffer.update(&data);
     }
 }
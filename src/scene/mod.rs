@@ -1,3 +1,16 @@
+pub mod astronomy;
+pub mod atmosphere;
+pub mod bookmarks;
 pub mod camera;
+pub mod clock;
+pub mod clouds;
 pub mod core;
+pub mod culling;
+pub mod depth;
+pub mod flight;
+pub mod geo;
+pub mod hdr;
 pub mod light;
+pub mod recording;
+pub mod shadows;
+pub mod stars;
@@ -0,0 +1,288 @@
+use crate::algebra::quat::Quat;
+use crate::algebra::vec3::Vec3;
+use crate::scene::camera::Camera;
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+// Playback advances by this much every update, whatever the frame rate.
+pub const PLAYBACK_STEP: f32 = 1.0 / 60.0;
+
+#[derive(Clone, Copy)]
+pub struct Keyframe {
+    pub time: f32,
+    pub position: Vec3,
+    pub yaw: f32,
+    pub pitch: f32,
+    pub fov: f32,
+}
+
+impl Keyframe {
+    fn orientation(&self) -> Quat {
+        Quat::from_yaw_pitch(self.yaw, self.pitch)
+    }
+}
+
+pub struct Recorder {
+    time: f32,
+    keyframes: Vec<Keyframe>,
+}
+
+impl Recorder {
+    pub fn new() -> Self {
+        Recorder {
+            time: 0.0,
+            keyframes: Vec::new(),
+        }
+    }
+
+    pub fn record(&mut self, delta_time: f32, camera: &Camera) {
+        if !self.keyframes.is_empty() {
+            self.time += delta_time;
+        }
+        self.keyframes.push(Keyframe {
+            time: self.time,
+            position: camera.position,
+            yaw: camera.yaw,
+            pitch: camera.pitch,
+            fov: camera.fov,
+        });
+    }
+
+    // One keyframe per line: `time x y z yaw pitch fov`.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut out = String::new();
+        for k in &self.keyframes {
+            out.push_str(&format!(
+                "{} {} {} {} {} {} {}\n",
+                k.time, k.position.x, k.position.y, k.position.z, k.yaw, k.pitch, k.fov
+            ));
+        }
+        fs::write(path, out)
+    }
+}
+
+pub struct Playback {
+    // Steps taken so far; the time is derived from it so it does not drift.
+    steps: u32,
+    finished: bool,
+    keyframes: Vec<Keyframe>,
+}
+
+impl Playback {
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let contents = fs::read_to_string(path)?;
+        let mut keyframes = Vec::new();
+
+        for (index, line) in contents.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let error = |message: &str| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("line {}: {}", index + 1, message),
+                )
+            };
+            let invalid = || error("expected `time x y z yaw pitch fov`");
+            let values = line
+                .split_whitespace()
+                .map(|v| v.parse::<f32>().map_err(|_| invalid()))
+                .collect::<io::Result<Vec<f32>>>()?;
+            if values.len() != 7 {
+                return Err(invalid());
+            }
+            // `nan` and `inf` parse too, but would keep playback from ever finishing.
+            if values.iter().any(|value| !value.is_finite()) {
+                return Err(error("values must be finite"));
+            }
+            // The keyframe lookup in `sample` relies on the times being sorted.
+            if keyframes
+                .last()
+                .is_some_and(|previous: &Keyframe| values[0] < previous.time)
+            {
+                return Err(error("time goes backwards"));
+            }
+
+            keyframes.push(Keyframe {
+                time: values[0],
+                position: Vec3::new(values[1], values[2], values[3]),
+                yaw: values[4],
+                pitch: values[5],
+                fov: values[6],
+            });
+        }
+
+        if keyframes.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "camera path has no keyframes",
+            ));
+        }
+
+        Ok(Playback {
+            steps: 0,
+            finished: false,
+            keyframes,
+        })
+    }
+
+    // True once the last keyframe has been applied.
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    // Moves the camera to the next fixed step of the path, the last one landing exactly on
+    // the final keyframe.
+    pub fn step(&mut self, camera: &mut Camera) {
+        let end = self.keyframes[self.keyframes.len() - 1].time;
+        let time = (self.steps as f32 * PLAYBACK_STEP).min(end);
+        let (position, orientation, fov) = self.sample(time);
+        camera.position = position;
+        camera.fov = fov;
+        camera.set_front(orientation.rotate(Vec3::new(1.0, 0.0, 0.0)));
+        self.steps += 1;
+        self.finished = time >= end;
+    }
+
+    fn sample(&self, time: f32) -> (Vec3, Quat, f32) {
+        let keys = &self.keyframes;
+        let last = keys.len() - 1;
+        let next = keys.partition_point(|k| k.time <= time);
+        if next == 0 || next > last {
+            let k = &keys[next.min(last)];
+            return (k.position, k.orientation(), k.fov);
+        }
+
+        let (i1, i2) = (next - 1, next);
+        let (i0, i3) = (i1.saturating_sub(1), (i2 + 1).min(last));
+        let (k0, k1, k2, k3) = (&keys[i0], &keys[i1], &keys[i2], &keys[i3]);
+
+        let span = k2.time - k1.time;
+        let t = if span > 0.0 {
+            (time - k1.time) / span
+        } else {
+            1.0
+        };
+
+        (
+            catmull_rom(k0, k1, k2, k3, t),
+            k1.orientation().slerp(&k2.orientation(), t),
+            k1.fov + (k2.fov - k1.fov) * t,
+        )
+    }
+}
+
+// Catmull-Rom segment between k1 and k2 with tangents scaled for uneven keyframe spacing.
+fn catmull_rom(k0: &Keyframe, k1: &Keyframe, k2: &Keyframe, k3: &Keyframe, t: f32) -> Vec3 {
+    let span = k2.time - k1.time;
+    let tangent = |a: &Keyframe, b: &Keyframe| {
+        let dt = b.time - a.time;
+        if dt > 0.0 {
+            (b.position - a.position) * (span / dt)
+        } else {
+            Vec3::new(0.0, 0.0, 0.0)
+        }
+    };
+    let m1 = tangent(k0, k2);
+    let m2 = tangent(k1, k3);
+
+    let t2 = t * t;
+    let t3 = t2 * t;
+    k1.position * (2.0 * t3 - 3.0 * t2 + 1.0)
+        + m1 * (t3 - 2.0 * t2 + t)
+        + k2.position * (-2.0 * t3 + 3.0 * t2)
+        + m2 * (t3 - t2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::env;
+
+    fn assert_near(a: f32, b: f32) {
+        assert!((a - b).abs() < 1e-3, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn replay_ends_on_the_last_keyframe() {
+        let mut camera = Camera::new(Vec3::new(0.0, 0.0, 3.0), Vec3::new(0.0, 1.0, 0.0));
+        let mut recorder = Recorder::new();
+        // Uneven frame times whose sum is not a multiple of PLAYBACK_STEP.
+        for frame in 0..50 {
+            let delta_time = [0.013, 0.021, 0.017][frame % 3];
+            camera.position += Vec3::new(0.03, 0.01, -0.02);
+            camera.set_front(Vec3::new(1.0, -0.2, frame as f32 * -0.05));
+            camera.fov = 45.0 - frame as f32 * 0.1;
+            recorder.record(delta_time, &camera);
+        }
+        let last = recorder.keyframes[recorder.keyframes.len() - 1];
+
+        let path = env::temp_dir().join(format!("earth-gl-path-{}.txt", std::process::id()));
+        recorder.save(&path).unwrap();
+        let mut playback = Playback::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let mut replayed = Camera::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+        let mut steps = 0;
+        while !playback.is_finished() {
+            playback.step(&mut replayed);
+            steps += 1;
+        }
+
+        assert_eq!(steps, (last.time / PLAYBACK_STEP).ceil() as u32 + 1);
+        let (p, q) = (replayed.position, last.position);
+        assert_near(p.x, q.x);
+        assert_near(p.y, q.y);
+        assert_near(p.z, q.z);
+        assert_near(replayed.yaw, last.yaw);
+        assert_near(replayed.pitch, last.pitch);
+        assert_near(replayed.fov, last.fov);
+    }
+
+    #[test]
+    fn single_keyframe_finishes_after_one_step() {
+        let mut camera = Camera::new(Vec3::new(1.0, 2.0, 3.0), Vec3::new(0.0, 1.0, 0.0));
+        let mut recorder = Recorder::new();
+        recorder.record(0.5, &camera);
+        let mut playback = Playback {
+            steps: 0,
+            finished: false,
+            keyframes: recorder.keyframes,
+        };
+
+        camera.position = Vec3::new(0.0, 0.0, 0.0);
+        playback.step(&mut camera);
+        assert!(playback.is_finished());
+        assert_eq!((camera.position.x, camera.position.y), (1.0, 2.0));
+    }
+
+    fn load_error(contents: &str, name: &str) -> String {
+        let path = env::temp_dir().join(format!("earth-gl-{}-{}.txt", name, std::process::id()));
+        fs::write(&path, contents).unwrap();
+        let result = Playback::load(&path);
+        fs::remove_file(&path).unwrap();
+        match result {
+            Err(err) if err.kind() == io::ErrorKind::InvalidData => err.to_string(),
+            Err(err) => panic!("unexpected error {}", err),
+            Ok(_) => panic!("the path was accepted"),
+        }
+    }
+
+    #[test]
+    fn non_finite_values_are_rejected() {
+        let contents = "0 0 0 3 -90 0 45\nnan 0 0 2 -90 0 45\n";
+        assert!(load_error(contents, "nan").starts_with("line 2:"));
+        let contents = "0 0 0 3 -90 0 45\n1 0 inf 2 -90 0 45\n";
+        assert!(load_error(contents, "inf").starts_with("line 2:"));
+    }
+
+    #[test]
+    fn decreasing_times_are_rejected() {
+        let contents = "0 0 0 3 -90 0 45\n1 0 0 2 -90 0 45\n\n0.5 0 0 1 -90 0 45\n";
+        assert!(load_error(contents, "unsorted").starts_with("line 4:"));
+    }
+}
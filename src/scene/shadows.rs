@@ -0,0 +1,244 @@
+use crate::algebra::mat4::Mat4;
+use crate::algebra::vec3::Vec3;
+use crate::core::constants::GLOBE_RADIUS;
+use crate::render::buffer::Mesh;
+use crate::render::framebuffer::{bind_default, Framebuffer};
+use crate::scene::camera::{look_at, Camera};
+use crate::scene::depth::DepthMode;
+use crate::shaders::error::ShaderError;
+use crate::shaders::program::ShaderProgram;
+use crate::textures::texture::Texture;
+
+pub const CASCADES: usize = 4;
+const SHADOW_MAP_SIZE: u32 = 2048;
+// Blend between uniform and logarithmic cascade splits.
+const SPLIT_LAMBDA: f32 = 0.8;
+// How far towards the sun each cascade reaches past its slice, so casters outside the
+// view still land in the map.
+const CASTER_MARGIN: f32 = 2.0 * GLOBE_RADIUS;
+
+// Cascaded shadow maps for the sun. Each cascade covers a slice of the view frustum,
+// enclosed in a sphere so the map does not change size as the camera turns.
+pub struct ShadowCascades {
+    pub enabled: bool,
+    // Far distance of each slice along the view direction.
+    splits: [f32; CASCADES],
+    light_matrices: [Mat4; CASCADES],
+    // Light view to shadow map texture space, for the receivers.
+    texture_matrices: [Mat4; CASCADES],
+    // World size of one shadow map texel, scales the receivers' normal offset.
+    texel_sizes: [f32; CASCADES],
+    framebuffer: Framebuffer,
+    depth: Texture,
+    shader_program: ShaderProgram,
+}
+
+impl ShadowCascades {
+    pub fn new() -> Result<Self, ShaderError> {
+        let depth = Texture::new(gl::TEXTURE_2D_ARRAY);
+        depth.image_3d(
+            gl::DEPTH_COMPONENT32F,
+            SHADOW_MAP_SIZE,
+            SHADOW_MAP_SIZE,
+            CASCADES as u32,
+            gl::DEPTH_COMPONENT,
+            gl::FLOAT,
+            None,
+        );
+        depth.set_filter(gl::LINEAR, gl::LINEAR);
+        depth.set_wrap(gl::CLAMP_TO_BORDER);
+        depth.set_border_color([1.0; 4]);
+        // Hardware depth comparison, filtered bilinearly for the PCF taps.
+        depth.set_parameter(gl::TEXTURE_COMPARE_MODE, gl::COMPARE_REF_TO_TEXTURE);
+        depth.set_parameter(gl::TEXTURE_COMPARE_FUNC, gl::LEQUAL);
+
+        let framebuffer = Framebuffer::new();
+        framebuffer.disable_color();
+        bind_default();
+
+        Ok(ShadowCascades {
+            enabled: true,
+            splits: [0.0; CASCADES],
+            light_matrices: std::array::from_fn(|_| Mat4::identity()),
+            texture_matrices: std::array::from_fn(|_| Mat4::identity()),
+            texel_sizes: [0.0; CASCADES],
+            framebuffer,
+            depth,
+            shader_program: ShaderProgram::new("shadow.vert", "shadow.frag")?,
+        })
+    }
+
+    pub fn shader_program(&self) -> &ShaderProgram {
+        &self.shader_program
+    }
+
+    pub fn shader_programs(&mut self) -> Vec<&mut ShaderProgram> {
+        vec![&mut self.shader_program]
+    }
+
+    pub fn toggle(&mut self) {
+        self.enabled = !self.enabled;
+    }
+
+    // Fits the cascades to the part of the view frustum up to the horizon, where shadows
+    // can be seen at all. `aspect` is the viewport's, so the slices cover what is on screen.
+    pub fn update(
+        &mut self,
+        camera: &Camera,
+        sun_direction: Vec3,
+        near: f32,
+        aspect: f32,
+        depth_mode: DepthMode,
+    ) {
+        let eye_distance = camera.position.length();
+        let horizon = (eye_distance * eye_distance - GLOBE_RADIUS * GLOBE_RADIUS)
+            .max(0.0)
+            .sqrt();
+        let far = (horizon + GLOBE_RADIUS).max(near * 2.0);
+
+        let tan_half_fov = (camera.fov.to_radians() / 2.0).tan();
+        let sun = sun_direction.normalize();
+        let up = if sun.y.abs() > 0.99 {
+            Vec3::new(1.0, 0.0, 0.0)
+        } else {
+            Vec3::new(0.0, 1.0, 0.0)
+        };
+
+        let mut slice_near = near;
+        for cascade in 0..CASCADES {
+            let fraction = (cascade + 1) as f32 / CASCADES as f32;
+            let logarithmic = near * (far / near).powf(fraction);
+            let uniform = near + (far - near) * fraction;
+            let slice_far = SPLIT_LAMBDA * logarithmic + (1.0 - SPLIT_LAMBDA) * uniform;
+
+            // Bounding sphere of the slice's eight corners.
+            let mut corners = Vec::with_capacity(8);
+            for distance in [slice_near, slice_far] {
+                let center = camera.position + camera.front * distance;
+                let half_height = distance * tan_half_fov;
+                let half_width = half_height * aspect;
+                for (x, y) in [(-1.0, -1.0), (1.0, -1.0), (-1.0, 1.0), (1.0, 1.0)] {
+                    corners.push(
+                        center + camera.right * (x * half_width) + camera.up * (y * half_height),
+                    );
+                }
+            }
+            let center = corners
+                .iter()
+                .fold(Vec3::new(0.0, 0.0, 0.0), |sum, corner| sum + *corner)
+                * (1.0 / corners.len() as f32);
+            let radius = corners
+                .iter()
+                .map(|corner| (*corner - center).length())
+                .fold(0.0, f32::max);
+
+            let depth = 2.0 * radius + CASTER_MARGIN;
+            let view = look_at(center + sun * (radius + CASTER_MARGIN), center, up);
+
+            // Moving the map in whole texels keeps shadow edges from crawling.
+            let texel = 2.0 * radius / SHADOW_MAP_SIZE as f32;
+            let offset_x = -view.row(0)[3].rem_euclid(texel);
+            let offset_y = -view.row(1)[3].rem_euclid(texel);
+
+            self.light_matrices[cascade] = &orthographic(
+                radius,
+                depth,
+                offset_x,
+                offset_y,
+                depth_mode == DepthMode::ReversedZ,
+            ) * &view;
+            self.texture_matrices[cascade] =
+                &shadow_texture_matrix(radius, depth, offset_x, offset_y) * &view;
+            self.texel_sizes[cascade] = texel;
+            self.splits[cascade] = slice_far;
+            slice_near = slice_far;
+        }
+    }
+
+    pub fn render(&self, casters: &[&Mesh], depth_mode: DepthMode) {
+        let program = &self.shader_program;
+        program.use_program();
+        program.set_mat4("model", &Mat4::identity());
+
+        // Casters must stay filled in wireframe mode, or the maps would only hold the edges.
+        let mut polygon_mode: [gl::types::GLint; 2] = [0; 2];
+        self.framebuffer.bind();
+        unsafe {
+            gl::GetIntegerv(gl::POLYGON_MODE, polygon_mode.as_mut_ptr());
+            gl::PolygonMode(gl::FRONT_AND_BACK, gl::FILL);
+            gl::Viewport(0, 0, SHADOW_MAP_SIZE as i32, SHADOW_MAP_SIZE as i32);
+            // Shadow maps use a plain depth range whatever the scene's depth mode.
+            gl::DepthFunc(gl::LESS);
+            gl::ClearDepth(1.0);
+            gl::Enable(gl::POLYGON_OFFSET_FILL);
+            gl::PolygonOffset(1.5, 2.0);
+        }
+
+        for (cascade, matrix) in self.light_matrices.iter().enumerate() {
+            self.framebuffer
+                .attach_layer(gl::DEPTH_ATTACHMENT, &self.depth, cascade as u32);
+            unsafe {
+                gl::Clear(gl::DEPTH_BUFFER_BIT);
+            }
+            program.set_mat4("lightSpace", matrix);
+            for caster in casters {
+                caster.draw();
+            }
+        }
+
+        unsafe {
+            gl::Disable(gl::POLYGON_OFFSET_FILL);
+            gl::PolygonMode(gl::FRONT_AND_BACK, polygon_mode[0] as gl::types::GLenum);
+        }
+        bind_default();
+        depth_mode.apply();
+    }
+
+    pub fn update_shader(&self, shader: &ShaderProgram, unit: u32) {
+        shader.set_bool("hasShadows", self.enabled);
+        if !self.enabled {
+            return;
+        }
+
+        self.depth.bind(unit);
+        shader.set_sampler("shadowMap", unit);
+        for cascade in 0..CASCADES {
+            shader.set_mat4(
+                &format!("shadowMatrices[{}]", cascade),
+                &self.texture_matrices[cascade],
+            );
+            shader.set_float(&format!("cascadeSplits[{}]", cascade), self.splits[cascade]);
+            shader.set_float(
+                &format!("shadowTexelSizes[{}]", cascade),
+                self.texel_sizes[cascade],
+            );
+        }
+    }
+}
+
+// Box of half extent `radius` in front of the light, `depth` deep, shifted by a sub-texel
+// offset. Depth maps to [0, 1] with glClipControl and to [-1, 1] without.
+fn orthographic(radius: f32, depth: f32, offset_x: f32, offset_y: f32, zero_to_one: bool) -> Mat4 {
+    let (z_scale, z_offset) = if zero_to_one {
+        (-1.0 / depth, 0.0)
+    } else {
+        (-2.0 / depth, -1.0)
+    };
+    Mat4::new([
+        [1.0 / radius, 0.0, 0.0, 0.0],
+        [0.0, 1.0 / radius, 0.0, 0.0],
+        [0.0, 0.0, z_scale, 0.0],
+        [offset_x / radius, offset_y / radius, z_offset, 1.0],
+    ])
+}
+
+// Same box as `orthographic`, mapped to texture coordinates and window depth in [0, 1].
+fn shadow_texture_matrix(radius: f32, depth: f32, offset_x: f32, offset_y: f32) -> Mat4 {
+    let scale = 0.5 / radius;
+    Mat4::new([
+        [scale, 0.0, 0.0, 0.0],
+        [0.0, scale, 0.0, 0.0],
+        [0.0, 0.0, -1.0 / depth, 0.0],
+        [0.5 + offset_x * scale, 0.5 + offset_y * scale, 0.0, 1.0],
+    ])
+}
@@ -0,0 +1,253 @@
+use crate::algebra::mat4::Mat4;
+use crate::algebra::vec3::Vec3;
+use crate::render::buffer::{Buffer, VertexArray};
+use crate::render::vertex::{Semantic, VertexLayout};
+use crate::scene::astronomy::{ecef_to_scene, inertial_to_ecef};
+use crate::scene::camera::CAMERA_BINDING;
+use crate::shaders::error::ShaderError;
+use crate::shaders::program::ShaderProgram;
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+const MAGNITUDE_LIMIT: f32 = 6.5;
+// B-V of the Sun, used when the catalogue has no colour index.
+const DEFAULT_COLOR_INDEX: f32 = 0.65;
+
+pub struct Star {
+    // J2000 right ascension and declination, in radians.
+    pub right_ascension: f32,
+    pub declination: f32,
+    pub magnitude: f32,
+    pub color_index: f32,
+}
+
+impl Star {
+    // Unit vector in the inertial equatorial frame.
+    pub fn direction(&self) -> Vec3 {
+        Vec3::new(
+            self.declination.cos() * self.right_ascension.cos(),
+            self.declination.cos() * self.right_ascension.sin(),
+            self.declination.sin(),
+        )
+    }
+
+    // Blackbody colour from B-V (Ballesteros 2012), then Tanner Helland's RGB fit.
+    pub fn color(&self) -> Vec3 {
+        let bv = self.color_index.clamp(-0.4, 2.0);
+        let temperature = 4600.0 * (1.0 / (0.92 * bv + 1.7) + 1.0 / (0.92 * bv + 0.62));
+        let t = temperature / 100.0;
+
+        let r = if t <= 66.0 {
+            255.0
+        } else {
+            329.698_73 * (t - 60.0).powf(-0.133_204_76)
+        };
+        let g = if t <= 66.0 {
+            99.470_8 * t.ln() - 161.119_57
+        } else {
+            288.122_17 * (t - 60.0).powf(-0.075_514_85)
+        };
+        let b = if t >= 66.0 {
+            255.0
+        } else if t <= 19.0 {
+            0.0
+        } else {
+            138.517_73 * (t - 10.0).ln() - 305.044_8
+        };
+
+        Vec3::new(
+            r.clamp(0.0, 255.0) / 255.0,
+            g.clamp(0.0, 255.0) / 255.0,
+            b.clamp(0.0, 255.0) / 255.0,
+        )
+    }
+
+    // Brightness relative to a magnitude 1 star, from Pogson's ratio.
+    pub fn intensity(&self) -> f32 {
+        10f32.powf(-0.4 * (self.magnitude - 1.0)).clamp(0.05, 1.0)
+    }
+
+    pub fn point_size(&self) -> f32 {
+        (4.5 - 0.6 * self.magnitude).clamp(1.0, 6.0)
+    }
+}
+
+// Reads the Yale Bright Star Catalogue, either the fixed-width `bsc5.dat` or a CSV
+// file with a header naming `ra` (hours), `dec` (degrees), `mag` and `bv`/`ci` columns.
+pub fn load_catalogue(path: &Path) -> io::Result<Vec<Star>> {
+    let contents = fs::read_to_string(path)?;
+    let stars = if path.extension().is_some_and(|ext| ext == "csv") {
+        parse_csv(&contents)?
+    } else {
+        parse_bsc5(&contents)
+    };
+
+    Ok(stars
+        .into_iter()
+        .filter(|star| star.magnitude <= MAGNITUDE_LIMIT)
+        .collect())
+}
+
+fn parse_bsc5(contents: &str) -> Vec<Star> {
+    let field = |line: &str, start: usize, end: usize| -> Option<f32> {
+        line.get(start..end)?.trim().parse().ok()
+    };
+
+    // Entries without coordinates (novae, removed objects) are skipped.
+    contents
+        .lines()
+        .filter_map(|line| {
+            let ra_hours = field(line, 75, 77)?;
+            let ra_minutes = field(line, 77, 79)?;
+            let ra_seconds = field(line, 79, 83)?;
+            let dec_sign = if line.get(83..84)? == "-" { -1.0 } else { 1.0 };
+            let dec_degrees = field(line, 84, 86)?;
+            let dec_minutes = field(line, 86, 88)?;
+            let dec_seconds = field(line, 88, 90)?;
+            let magnitude = field(line, 102, 107)?;
+            let color_index = field(line, 109, 114).unwrap_or(DEFAULT_COLOR_INDEX);
+
+            let ra = ra_hours + ra_minutes / 60.0 + ra_seconds / 3600.0;
+            let dec = dec_degrees + dec_minutes / 60.0 + dec_seconds / 3600.0;
+            Some(Star {
+                right_ascension: (ra * 15.0).to_radians(),
+                declination: (dec_sign * dec).to_radians(),
+                magnitude,
+                color_index,
+            })
+        })
+        .collect()
+}
+
+fn parse_csv(contents: &str) -> io::Result<Vec<Star>> {
+    let mut lines = contents.lines();
+    let header = lines.next().unwrap_or_default();
+    let columns: Vec<String> = header
+        .split(',')
+        .map(|c| c.trim().trim_matches('"').to_lowercase())
+        .collect();
+    let column = |names: &[&str]| columns.iter().position(|c| names.contains(&c.as_str()));
+
+    let missing = |name: &str| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("star catalogue has no `{}` column", name),
+        )
+    };
+    let ra = column(&["ra"]).ok_or_else(|| missing("ra"))?;
+    let dec = column(&["dec"]).ok_or_else(|| missing("dec"))?;
+    let mag = column(&["mag", "vmag"]).ok_or_else(|| missing("mag"))?;
+    let bv = column(&["bv", "ci", "b-v"]);
+
+    let mut stars = Vec::new();
+    for line in lines {
+        let values: Vec<&str> = line
+            .split(',')
+            .map(|v| v.trim().trim_matches('"'))
+            .collect();
+        let value = |index: usize| values.get(index).and_then(|v| v.parse::<f32>().ok());
+
+        if let (Some(ra), Some(dec), Some(magnitude)) = (value(ra), value(dec), value(mag)) {
+            stars.push(Star {
+                right_ascension: (ra * 15.0).to_radians(),
+                declination: dec.to_radians(),
+                magnitude,
+                color_index: bv.and_then(value).unwrap_or(DEFAULT_COLOR_INDEX),
+            });
+        }
+    }
+
+    Ok(stars)
+}
+
+pub struct Starfield {
+    shader_program: ShaderProgram,
+    vertex_array: VertexArray,
+    _vertex_buffer: Buffer,
+    count: usize,
+}
+
+impl Starfield {
+    pub fn new(stars: &[Star]) -> Result<Self, ShaderError> {
+        let layout = VertexLayout::new()
+            .with(Semantic::Direction, 3)
+            .with(Semantic::Color, 3)
+            .with(Semantic::Size, 1);
+        let mut vertices = Vec::with_capacity(stars.len() * layout.floats_per_vertex());
+        for star in stars {
+            let direction = star.direction();
+            let color = star.color() * star.intensity();
+            vertices.extend_from_slice(&[
+                direction.x,
+                direction.y,
+                direction.z,
+                color.x,
+                color.y,
+                color.z,
+                star.point_size(),
+            ]);
+        }
+
+        let mut shader_program = ShaderProgram::new("stars.vert", "stars.frag")?;
+        shader_program.bind_uniform_block("Camera", CAMERA_BINDING);
+        layout.validate(&shader_program)?;
+
+        let vertex_array = VertexArray::new();
+        vertex_array.bind();
+        let vertex_buffer = Buffer::new(gl::ARRAY_BUFFER);
+        vertex_buffer.upload(&vertices, gl::STATIC_DRAW);
+        layout.apply();
+
+        Ok(Starfield {
+            shader_program,
+            vertex_array,
+            _vertex_buffer: vertex_buffer,
+            count: stars.len(),
+        })
+    }
+
+    pub fn shader_programs(&mut self) -> Vec<&mut ShaderProgram> {
+        vec![&mut self.shader_program]
+    }
+
+    // Drawn first, without depth, so everything else in the scene covers it.
+    pub fn render(&self, unix_seconds: f64) {
+        self.shader_program.use_program();
+        self.shader_program
+            .set_mat4("model", &inertial_to_scene(unix_seconds));
+
+        unsafe {
+            gl::Disable(gl::DEPTH_TEST);
+            gl::Enable(gl::PROGRAM_POINT_SIZE);
+            gl::Enable(gl::BLEND);
+            gl::BlendFunc(gl::ONE, gl::ONE);
+        }
+
+        self.vertex_array.draw_arrays(gl::POINTS, self.count);
+
+        unsafe {
+            gl::Disable(gl::BLEND);
+            gl::Disable(gl::PROGRAM_POINT_SIZE);
+            gl::Enable(gl::DEPTH_TEST);
+        }
+    }
+}
+
+// Rotation from the inertial frame to the scene, following Earth's rotation.
+fn inertial_to_scene(unix_seconds: f64) -> Mat4 {
+    let axis = |i: usize| {
+        let mut basis = [0.0; 3];
+        basis[i] = 1.0;
+        ecef_to_scene(inertial_to_ecef(basis, unix_seconds))
+    };
+    let (x, y, z) = (axis(0), axis(1), axis(2));
+
+    Mat4::new([
+        [x.x, x.y, x.z, 0.0],
+        [y.x, y.y, y.z, 0.0],
+        [z.x, z.y, z.z, 0.0],
+        [0.0, 0.0, 0.0, 1.0],
+    ])
+}
@@ -0,0 +1,133 @@
+#version 330 core
+in vec2 ScreenPos;
+
+// Blended with glBlendFunc(GL_ONE, GL_SRC1_COLOR): scene * transmittance + in-scattering.
+layout (location = 0, index = 0) out vec4 FragColor;
+layout (location = 0, index = 1) out vec4 Transmittance;
+
+uniform vec3 viewPos;
+uniform vec3 cameraFront;
+uniform vec3 cameraRight;
+uniform vec3 cameraUp;
+uniform float tanHalfFov;
+uniform float aspect;
+
+uniform vec3 lightDir;
+uniform vec3 lightColor;
+
+uniform float planetRadius;
+uniform float atmosphereRadius;
+uniform vec3 rayleighScattering;
+uniform float rayleighScaleHeight;
+uniform float mieScattering;
+uniform float mieScaleHeight;
+uniform float mieAnisotropy;
+
+uniform sampler2D sceneDepth;
+uniform bool logDepth;
+uniform float logDepthCoef;
+uniform float logDepthScale;
+uniform float nearPlane;
+
+#include "include/common.glsl"
+
+const int VIEW_SAMPLES = 16;
+const int LIGHT_SAMPLES = 8;
+const float NO_HIT = 1e9;
+
+vec2 raySphere(vec3 origin, vec3 dir, float radius) {
+    float b = dot(origin, dir);
+    float c = dot(origin, origin) - radius * radius;
+    float d = b * b - c;
+    if (d < 0.0) {
+        return vec2(NO_HIT, -NO_HIT);
+    }
+    d = sqrt(d);
+    return vec2(-b - d, -b + d);
+}
+
+// Distance along the view ray to whatever was drawn in the scene pass.
+float sceneDistance(float viewCos) {
+    float depth = texture(sceneDepth, ScreenPos * 0.5 + 0.5).r;
+    float viewZ;
+    if (logDepth) {
+        if (depth >= 1.0) {
+            return NO_HIT;
+        }
+        viewZ = (exp2(depth * 2.0 / logDepthCoef) - 1.0) / logDepthScale;
+    } else {
+        if (depth <= 0.0) {
+            return NO_HIT;
+        }
+        viewZ = nearPlane / depth;
+    }
+    return viewZ / viewCos;
+}
+
+vec2 densities(vec3 pos) {
+    float height = max(length(pos) - planetRadius, 0.0);
+    return vec2(exp(-height / rayleighScaleHeight), exp(-height / mieScaleHeight));
+}
+
+vec3 extinction(vec2 opticalDepth) {
+    return rayleighScattering * opticalDepth.x + 1.1 * mieScattering * opticalDepth.y;
+}
+
+void main() {
+    vec3 dir = normalize(cameraFront
+        + ScreenPos.x * tanHalfFov * aspect * cameraRight
+        + ScreenPos.y * tanHalfFov * cameraUp);
+
+    vec2 atmosphere = raySphere(viewPos, dir, atmosphereRadius);
+    float start = max(atmosphere.x, 0.0);
+    float end = min(atmosphere.y, sceneDistance(dot(dir, cameraFront)));
+    vec2 planet = raySphere(viewPos, dir, planetRadius);
+    if (planet.x > 0.0) {
+        end = min(end, planet.x);
+    }
+
+    if (end <= start) {
+        FragColor = vec4(0.0);
+        Transmittance = vec4(1.0);
+        return;
+    }
+
+    vec3 sunDir = normalize(lightDir);
+    float mu = dot(dir, sunDir);
+    float g = mieAnisotropy;
+    float rayleighPhase = 3.0 / (16.0 * PI) * (1.0 + mu * mu);
+    float miePhase = 3.0 / (8.0 * PI) * ((1.0 - g * g) * (1.0 + mu * mu))
+        / ((2.0 + g * g) * pow(1.0 + g * g - 2.0 * g * mu, 1.5));
+
+    float stepSize = (end - start) / float(VIEW_SAMPLES);
+    vec2 opticalDepth = vec2(0.0);
+    vec3 rayleighSum = vec3(0.0);
+    vec3 mieSum = vec3(0.0);
+
+    for (int i = 0; i < VIEW_SAMPLES; i++) {
+        vec3 pos = viewPos + dir * (start + (float(i) + 0.5) * stepSize);
+        vec2 density = densities(pos) * stepSize;
+        opticalDepth += density;
+
+        // Samples in the planet's shadow receive no direct sunlight.
+        if (raySphere(pos, sunDir, planetRadius).x > 0.0) {
+            continue;
+        }
+
+        float lightStep = raySphere(pos, sunDir, atmosphereRadius).y / float(LIGHT_SAMPLES);
+        vec2 lightDepth = vec2(0.0);
+        for (int j = 0; j < LIGHT_SAMPLES; j++) {
+            lightDepth += densities(pos + sunDir * (float(j) + 0.5) * lightStep) * lightStep;
+        }
+
+        vec3 attenuation = exp(-extinction(opticalDepth + lightDepth));
+        rayleighSum += attenuation * density.x;
+        mieSum += attenuation * density.y;
+    }
+
+    vec3 inScattering = lightColor
+        * (rayleighSum * rayleighScattering * rayleighPhase + mieSum * mieScattering * miePhase);
+
+    FragColor = vec4(inScattering, 1.0);
+    Transmittance = vec4(exp(-extinction(opticalDepth)), 1.0);
+}
@@ -0,0 +1,15 @@
+#version 330 core
+in vec2 ScreenPos;
+
+out vec4 FragColor;
+
+uniform sampler2D source;
+uniform float threshold;
+
+void main() {
+    // Level 0 explicitly, the HDR target's mips are only refreshed for exposure metering.
+    vec3 color = textureLod(source, ScreenPos * 0.5 + 0.5, 0.0).rgb;
+    float luminance = dot(color, vec3(0.2126, 0.7152, 0.0722));
+    float excess = max(luminance - threshold, 0.0);
+    FragColor = vec4(color * excess / max(luminance, 1e-4), 1.0);
+}
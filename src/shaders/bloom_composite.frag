@@ -0,0 +1,12 @@
+#version 330 core
+in vec2 ScreenPos;
+
+out vec4 FragColor;
+
+uniform sampler2D source;
+uniform float intensity;
+
+// Added onto the scene with glBlendFunc(GL_ONE, GL_ONE).
+void main() {
+    FragColor = vec4(texture(source, ScreenPos * 0.5 + 0.5).rgb * intensity, 1.0);
+}
@@ -0,0 +1,71 @@
+use crate::shaders::error::ShaderError;
+use crate::shaders::program::ShaderProgram;
+
+// One build of a program: its two sources and the defines injected into both.
+#[derive(Clone, PartialEq, Eq)]
+pub struct VariantKey {
+    pub vertex: String,
+    pub fragment: String,
+    // Sorted by name, so the order they were added in does not matter.
+    defines: Vec<(String, String)>,
+}
+
+impl VariantKey {
+    pub fn new(vertex: &str, fragment: &str) -> Self {
+        VariantKey {
+            vertex: vertex.to_string(),
+            fragment: fragment.to_string(),
+            defines: Vec::new(),
+        }
+    }
+
+    // Adds `#define name value`, replacing an earlier value for the same name.
+    pub fn define(mut self, name: &str, value: impl ToString) -> Self {
+        let value = value.to_string();
+        match self
+            .defines
+            .binary_search_by(|(existing, _)| existing.as_str().cmp(name))
+        {
+            Ok(index) => self.defines[index].1 = value,
+            Err(index) => self.defines.insert(index, (name.to_string(), value)),
+        }
+        self
+    }
+}
+
+// Handle to a program in a `ProgramCache`.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct ProgramId(usize);
+
+// Compiles each variant once, however many users ask for it.
+pub struct ProgramCache {
+    programs: Vec<(VariantKey, ShaderProgram)>,
+}
+
+impl ProgramCache {
+    pub fn new() -> Self {
+        ProgramCache {
+            programs: Vec::new(),
+        }
+    }
+
+    pub fn get_or_build(&mut self, key: &VariantKey) -> Result<ProgramId, ShaderError> {
+        if let Some(index) = self.programs.iter().position(|(cached, _)| cached == key) {
+            return Ok(ProgramId(index));
+        }
+        let program = ShaderProgram::with_defines(&key.vertex, &key.fragment, &key.defines)?;
+        self.programs.push((key.clone(), program));
+        Ok(ProgramId(self.programs.len() - 1))
+    }
+
+    pub fn get(&self, id: ProgramId) -> &ShaderProgram {
+        &self.programs[id.0].1
+    }
+
+    pub fn shader_programs(&mut self) -> Vec<&mut ShaderProgram> {
+        self.programs
+            .iter_mut()
+            .map(|(_, program)| program)
+            .collect()
+    }
+}
@@ -0,0 +1,29 @@
+#version 330 core
+in vec3 FragPos;
+in vec3 Normal;
+in vec3 LocalNormal;
+in float LogZ;
+
+out vec4 FragColor;
+
+uniform vec3 lightDir;
+uniform vec3 lightColor;
+uniform sampler2D clouds;
+
+// Same mapping as the globe, the shell rotates through its model matrix.
+#include "include/common.glsl"
+#include "include/log_depth.glsl"
+
+void main() {
+    float coverage = texture(clouds, globeUV(normalize(LocalNormal))).r;
+    // Keep clear sky out of the depth buffer so the atmosphere still sees the ground.
+    if (coverage < 0.05) {
+        discard;
+    }
+
+    float diff = max(dot(normalize(Normal), normalize(lightDir)), 0.0);
+    vec3 color = (0.05 + diff) * lightColor / PI;
+    FragColor = vec4(color, coverage);
+
+    gl_FragDepth = fragmentDepth(LogZ);
+}
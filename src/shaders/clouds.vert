@@ -0,0 +1,21 @@
+#version 330 core
+in vec3 aPos;
+in vec3 aNormal;
+
+out vec3 FragPos;
+out vec3 Normal;
+out vec3 LocalNormal;
+out float LogZ;
+
+uniform mat4 model;
+
+#include "include/camera.glsl"
+
+void main() {
+    FragPos = vec3(model * vec4(aPos, 1.0));
+    Normal = mat3(model) * aNormal;
+    LocalNormal = aNormal;
+
+    gl_Position = projection * view * vec4(FragPos, 1.0);
+    LogZ = 1.0 + logDepthScale * gl_Position.w;
+}
@@ -0,0 +1,16 @@
+#version 330 core
+in vec2 ScreenPos;
+
+out vec4 FragColor;
+
+uniform sampler2D source;
+uniform sampler3D lut;
+uniform float lutSize;
+uniform float strength;
+
+void main() {
+    vec3 color = texture(source, ScreenPos * 0.5 + 0.5).rgb;
+    // Sample texel centres so the ends of the table map to exactly 0 and 1.
+    vec3 coord = clamp(color, 0.0, 1.0) * (lutSize - 1.0) / lutSize + 0.5 / lutSize;
+    FragColor = vec4(mix(color, texture(lut, coord).rgb, strength), 1.0);
+}
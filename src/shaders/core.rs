@@ -1,35 +1,504 @@
-use std::ffi::CString;
-use std::fs::File;
-use std::io::Read;
+use crate::shaders::error::{parse_locations, ShaderError, ShaderStage, SourceLocation};
+use crate::shaders::registry::{self, ShaderFile};
+
+use gl::types::{GLint, GLuint};
+
+use std::ffi::{CStr, CString};
+use std::io;
+use std::path::PathBuf;
 use std::ptr;
 
-pub fn load_shader(path: &str, shader_type: gl::types::GLenum) -> gl::types::GLuint {
-    let mut file = File::open(path).unwrap();
-    let mut shader_source = String::new();
-    file.read_to_string(&mut shader_source).unwrap();
+// Shader text ready for the driver, with the files it was assembled from in source string
+// order: the `#line` directives refer to them by index.
+pub struct PreprocessedSource {
+    pub text: String,
+    pub files: Vec<PathBuf>,
+}
+
+// Resolves `#include "file.glsl"` relative to the including shader and injects `defines`
+// right after `#version`. Each file is included once per shader, which also breaks cycles.
+// The files read from disk rather than the binary, which can change while running, are
+// added to `watched` even when preprocessing fails.
+pub fn preprocess(
+    name: &str,
+    defines: &[(String, String)],
+    watched: &mut Vec<PathBuf>,
+) -> Result<PreprocessedSource, ShaderError> {
+    preprocess_with(name, defines, &registry::load, watched)
+}
+
+// As `preprocess`, reading the files through `load`.
+fn preprocess_with(
+    name: &str,
+    defines: &[(String, String)],
+    load: &dyn Fn(&str) -> Result<ShaderFile, ShaderError>,
+    watched: &mut Vec<PathBuf>,
+) -> Result<PreprocessedSource, ShaderError> {
+    let mut preprocessor = Preprocessor {
+        output: PreprocessedSource {
+            text: String::new(),
+            files: Vec::new(),
+        },
+        included: vec![name.to_string()],
+        load,
+        watched,
+    };
+    let file = preprocessor.read(name)?;
+
+    let mut body = file.text.as_str();
+    let mut first_line = 1;
+    if let Some((version, rest)) = file.text.split_once('\n') {
+        if version.trim_start().starts_with("#version") {
+            preprocessor.output.text.push_str(version);
+            preprocessor.output.text.push('\n');
+            body = rest;
+            first_line = 2;
+        }
+    }
+    for (name, value) in defines {
+        preprocessor
+            .output
+            .text
+            .push_str(&format!("#define {} {}\n", name, value));
+    }
+    preprocessor
+        .output
+        .text
+        .push_str(&format!("#line {} 0\n", first_line));
+
+    preprocessor.expand(name, body, first_line, 0)?;
+    Ok(preprocessor.output)
+}
+
+struct Preprocessor<'a> {
+    output: PreprocessedSource,
+    // Registry names, normalised so the same file reached through different relative
+    // paths is still only included once.
+    included: Vec<String>,
+    load: &'a dyn Fn(&str) -> Result<ShaderFile, ShaderError>,
+    watched: &'a mut Vec<PathBuf>,
+}
+
+impl Preprocessor<'_> {
+    fn read(&mut self, name: &str) -> Result<ShaderFile, ShaderError> {
+        let file = (self.load)(name)?;
+        self.output.files.push(file.display_path(name));
+        if let Some(path) = &file.path {
+            self.watched.push(path.clone());
+        }
+        Ok(file)
+    }
+
+    fn expand(
+        &mut self,
+        name: &str,
+        contents: &str,
+        first_line: u32,
+        index: u32,
+    ) -> Result<(), ShaderError> {
+        for (offset, line) in contents.lines().enumerate() {
+            let line_number = first_line + offset as u32;
+            let Some(directive) = line.trim_start().strip_prefix("#include") else {
+                self.output.text.push_str(line);
+                self.output.text.push('\n');
+                continue;
+            };
+
+            let include = directive
+                .trim()
+                .strip_prefix('"')
+                .and_then(|rest| rest.strip_suffix('"'))
+                .ok_or_else(|| ShaderError::Preprocess {
+                    location: SourceLocation {
+                        path: self.output.files[index as usize].clone(),
+                        line: line_number,
+                    },
+                    message: "expected #include \"file\"".to_string(),
+                })?;
+            let include_name = registry::resolve(name, include);
+
+            if !self.included.contains(&include_name) {
+                self.included.push(include_name.clone());
+                let include_index = self.output.files.len() as u32;
+                let included = self.read(&include_name)?;
+                self.output
+                    .text
+                    .push_str(&format!("#line 1 {}\n", include_index));
+                self.expand(&include_name, &included.text, 1, include_index)?;
+            }
+            self.output
+                .text
+                .push_str(&format!("#line {} {}\n", line_number + 1, index));
+        }
+        Ok(())
+    }
+}
+
+// Compiles one stage from the shader registry. The files to watch for changes are added to
+// `watched`, also when the stage fails to build.
+pub fn load_shader(
+    name: &str,
+    stage: ShaderStage,
+    defines: &[(String, String)],
+    watched: &mut Vec<PathBuf>,
+) -> Result<Shader, ShaderError> {
+    let source = preprocess(name, defines, watched)?;
+    let c_str = CString::new(source.text).map_err(|err| ShaderError::Io {
+        path: source.files[0].clone(),
+        source: io::Error::new(io::ErrorKind::InvalidData, err),
+    })?;
 
+    Shader::compile(stage, &c_str).map_err(|log| ShaderError::Compile {
+        stage,
+        path: source.files[0].clone(),
+        locations: locate(&log, &source.files),
+        files: source.files,
+        log,
+    })
+}
+
+// Where the driver's log reports errors, as lines of `files` in source string order.
+fn locate(log: &str, files: &[PathBuf]) -> Vec<SourceLocation> {
+    parse_locations(log)
+        .into_iter()
+        .filter_map(|(index, line)| {
+            Some(SourceLocation {
+                path: files.get(index as usize)?.clone(),
+                line,
+            })
+        })
+        .collect()
+}
+
+// A compiled shader stage, deleted on drop.
+pub struct Shader {
+    id: GLuint,
+}
+
+impl Shader {
+    // Returns the driver's log on failure.
+    pub fn compile(stage: ShaderStage, source: &CStr) -> Result<Self, String> {
+        let shader = unsafe {
+            let id = gl::CreateShader(stage.gl_enum());
+            gl::ShaderSource(id, 1, &source.as_ptr(), ptr::null());
+            gl::CompileShader(id);
+            Shader { id }
+        };
+
+        let mut success = gl::FALSE as GLint;
+        unsafe {
+            gl::GetShaderiv(shader.id, gl::COMPILE_STATUS, &mut success);
+        }
+        if success != gl::TRUE as GLint {
+            return Err(shader_info_log(shader.id));
+        }
+        Ok(shader)
+    }
+}
+
+impl Drop for Shader {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteShader(self.id);
+        }
+    }
+}
+
+// A linked GL program, deleted on drop.
+pub struct Program {
+    id: GLuint,
+}
+
+impl Program {
+    // `attributes` are bound to their locations before linking. Returns the driver's log on
+    // failure.
+    pub fn link(shaders: &[&Shader], attributes: &[(&str, u32)]) -> Result<Self, String> {
+        let program = Program {
+            id: unsafe { gl::CreateProgram() },
+        };
+        unsafe {
+            for shader in shaders {
+                gl::AttachShader(program.id, shader.id);
+            }
+            for (name, location) in attributes {
+                let c_name = CString::new(*name).unwrap();
+                gl::BindAttribLocation(program.id, *location, c_name.as_ptr());
+            }
+            gl::LinkProgram(program.id);
+            for shader in shaders {
+                gl::DetachShader(program.id, shader.id);
+            }
+        }
+
+        let mut success = gl::FALSE as GLint;
+        unsafe {
+            gl::GetProgramiv(program.id, gl::LINK_STATUS, &mut success);
+        }
+        if success != gl::TRUE as GLint {
+            return Err(program_info_log(program.id));
+        }
+        Ok(program)
+    }
+
+    pub fn id(&self) -> GLuint {
+        self.id
+    }
+
+    pub fn use_program(&self) {
+        unsafe {
+            gl::UseProgram(self.id);
+        }
+    }
+
+    // -1 when `name` is not an active uniform outside a block.
+    pub fn uniform_location(&self, name: &str) -> GLint {
+        let c_name = CString::new(name).unwrap();
+        unsafe { gl::GetUniformLocation(self.id, c_name.as_ptr()) }
+    }
+
+    pub fn bind_uniform_block(&self, index: GLuint, binding: u32) {
+        unsafe {
+            gl::UniformBlockBinding(self.id, index, binding);
+        }
+    }
+}
+
+impl Drop for Program {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteProgram(self.id);
+        }
+    }
+}
+
+fn shader_info_log(shader: GLuint) -> String {
+    let mut length = 0;
+    let mut written = 0;
+    let mut log;
     unsafe {
-        let shader = gl::CreateShader(shader_type);
-        let c_str = CString::new(shader_source.as_bytes()).unwrap();
-        gl::ShaderSource(shader, 1, &c_str.as_ptr(), ptr::null());
-        gl::CompileShader(shader);
-
-        let mut success = gl::FALSE as gl::types::GLint;
-        let mut info_log = Vec::with_capacity(512 - 1);
-        gl::GetShaderiv(shader, gl::COMPILE_STATUS, &mut success);
-        if success != gl::TRUE as gl::types::GLint {
-            gl::GetShaderInfoLog(
-                shader,
-                512,
-                ptr::null_mut(),
-                info_log.as_mut_ptr() as *mut gl::types::GLchar,
-            );
-            println!(
-                "ERROR::SHADER::COMPILATION_FAILED\n{}",
-                String::from_utf8_lossy(&info_log)
+        gl::GetShaderiv(shader, gl::INFO_LOG_LENGTH, &mut length);
+        log = vec![0u8; length.max(1) as usize];
+        gl::GetShaderInfoLog(
+            shader,
+            length,
+            &mut written,
+            log.as_mut_ptr() as *mut gl::types::GLchar,
+        );
+    }
+    log.truncate(written.max(0) as usize);
+    String::from_utf8_lossy(&log).into_owned()
+}
+
+fn program_info_log(program: GLuint) -> String {
+    let mut length = 0;
+    let mut written = 0;
+    let mut log;
+    unsafe {
+        gl::GetProgramiv(program, gl::INFO_LOG_LENGTH, &mut length);
+        log = vec![0u8; length.max(1) as usize];
+        gl::GetProgramInfoLog(
+            program,
+            length,
+            &mut written,
+            log.as_mut_ptr() as *mut gl::types::GLchar,
+        );
+    }
+    log.truncate(written.max(0) as usize);
+    String::from_utf8_lossy(&log).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FILES: &[(&str, &str)] = &[
+        (
+            "main.frag",
+            "#version 330 core\nuniform float scale;\n#include \"include/outer.glsl\"\n\
+             #include \"include/inner.glsl\"\nvoid main() {}\n",
+        ),
+        (
+            "include/outer.glsl",
+            "// outer\n#include \"inner.glsl\"\nfloat outer() { return inner(); }\n",
+        ),
+        (
+            "include/inner.glsl",
+            "// inner\nfloat inner() {\n    return undeclared;\n}\n",
+        ),
+        (
+            "include/a.glsl",
+            "#include \"b.glsl\"\nfloat a() { return 1.0; }\n",
+        ),
+        (
+            "include/b.glsl",
+            "#include \"a.glsl\"\nfloat b() { return 2.0; }\n",
+        ),
+        (
+            "cycle.vert",
+            "#include \"include/a.glsl\"\n#include \"include/b.glsl\"\nvoid main() {}\n",
+        ),
+    ];
+
+    fn load(name: &str) -> Result<ShaderFile, ShaderError> {
+        FILES
+            .iter()
+            .find(|(file, _)| *file == name)
+            .map(|(_, text)| ShaderFile {
+                text: text.to_string(),
+                path: None,
+            })
+            .ok_or_else(|| ShaderError::Io {
+                path: PathBuf::from(name),
+                source: io::Error::new(io::ErrorKind::NotFound, "no such shader"),
+            })
+    }
+
+    fn source(name: &str) -> &'static str {
+        FILES.iter().find(|(file, _)| *file == name).unwrap().1
+    }
+
+    // Follows the `#line` directives as the driver does: every other line of `text` with
+    // its source string index and line number.
+    fn driver_lines(text: &str) -> Vec<(u32, u32, &str)> {
+        let (mut index, mut line) = (0, 1);
+        let mut lines = Vec::new();
+        for text in text.lines() {
+            if let Some(directive) = text.strip_prefix("#line ") {
+                let mut numbers = directive.split(' ').map(|n| n.parse::<u32>().unwrap());
+                line = numbers.next().unwrap();
+                index = numbers.next().unwrap_or(index);
+                continue;
+            }
+            lines.push((index, line, text));
+            line += 1;
+        }
+        lines
+    }
+
+    #[test]
+    fn line_directives_map_back_to_the_original_files() {
+        let output = preprocess_with("main.frag", &[], &load, &mut Vec::new()).unwrap();
+        let names: Vec<_> = output
+            .files
+            .iter()
+            .map(|path| path.to_str().unwrap())
+            .collect();
+        assert_eq!(
+            names,
+            ["main.frag", "include/outer.glsl", "include/inner.glsl"]
+        );
+
+        for (index, line, text) in driver_lines(&output.text) {
+            let original = source(names[index as usize]);
+            assert_eq!(
+                original.lines().nth(line as usize - 1),
+                Some(text),
+                "{}:{}",
+                names[index as usize],
+                line
             );
         }
+    }
+
+    #[test]
+    fn nested_compile_errors_point_at_the_included_file() {
+        let output = preprocess_with("main.frag", &[], &load, &mut Vec::new()).unwrap();
+        let (index, line, _) = driver_lines(&output.text)
+            .into_iter()
+            .find(|(_, _, text)| text.contains("undeclared"))
+            .unwrap();
+        let log = format!("{}:{}(12): error: `undeclared' undeclared\n", index, line);
+
+        assert_eq!(
+            locate(&log, &output.files),
+            [SourceLocation {
+                path: PathBuf::from("include/inner.glsl"),
+                line: 3,
+            }]
+        );
+    }
+
+    #[test]
+    fn files_are_included_once_and_cycles_end() {
+        let output = preprocess_with("cycle.vert", &[], &load, &mut Vec::new()).unwrap();
+        let names: Vec<_> = output
+            .files
+            .iter()
+            .map(|path| path.to_str().unwrap())
+            .collect();
+        assert_eq!(names, ["cycle.vert", "include/a.glsl", "include/b.glsl"]);
+        assert_eq!(output.text.matches("float a()").count(), 1);
+        assert_eq!(output.text.matches("float b()").count(), 1);
+    }
+
+    #[test]
+    fn defines_follow_the_version_line() {
+        let defines = [
+            ("SHADING".to_string(), "2".to_string()),
+            ("HAS_MAP".to_string(), "1".to_string()),
+        ];
+        let output = preprocess_with("main.frag", &defines, &load, &mut Vec::new()).unwrap();
+        let lines: Vec<_> = output.text.lines().take(4).collect();
+        assert_eq!(
+            lines,
+            [
+                "#version 330 core",
+                "#define SHADING 2",
+                "#define HAS_MAP 1",
+                "#line 2 0"
+            ]
+        );
+
+        // Without a #version line the defines come first and numbering starts at 1.
+        let output = preprocess_with("cycle.vert", &defines, &load, &mut Vec::new()).unwrap();
+        let lines: Vec<_> = output.text.lines().take(3).collect();
+        assert_eq!(
+            lines,
+            ["#define SHADING 2", "#define HAS_MAP 1", "#line 1 0"]
+        );
+    }
+
+    #[test]
+    fn malformed_includes_report_their_line() {
+        let load = |name: &str| {
+            Ok(ShaderFile {
+                text: format!("// {}\n#include <common.glsl>\n", name),
+                path: None,
+            })
+        };
+        match preprocess_with("broken.frag", &[], &load, &mut Vec::new()) {
+            Err(ShaderError::Preprocess { location, .. }) => assert_eq!(
+                location,
+                SourceLocation {
+                    path: PathBuf::from("broken.frag"),
+                    line: 2,
+                }
+            ),
+            _ => panic!("the include was accepted"),
+        }
+    }
 
-        shader
+    #[test]
+    fn files_read_before_a_failure_are_watched() {
+        // Files from disk carry their path, the missing include fails.
+        let load = |name: &str| match name {
+            "main.frag" | "include/new.glsl" => Ok(ShaderFile {
+                text: format!(
+                    "// {}\n#include \"include/new.glsl\"\n#include \"gone.glsl\"\n",
+                    name
+                ),
+                path: Some(PathBuf::from("/shaders").join(name)),
+            }),
+            _ => load(name),
+        };
+        let mut watched = Vec::new();
+        assert!(preprocess_with("main.frag", &[], &load, &mut watched).is_err());
+        assert_eq!(
+            watched,
+            [
+                PathBuf::from("/shaders/main.frag"),
+                PathBuf::from("/shaders/include/new.glsl")
+            ]
+        );
     }
 }
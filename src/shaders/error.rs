@@ -0,0 +1,191 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ShaderStage {
+    Vertex,
+    Fragment,
+}
+
+impl ShaderStage {
+    pub fn gl_enum(self) -> gl::types::GLenum {
+        match self {
+            ShaderStage::Vertex => gl::VERTEX_SHADER,
+            ShaderStage::Fragment => gl::FRAGMENT_SHADER,
+        }
+    }
+}
+
+impl fmt::Display for ShaderStage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ShaderStage::Vertex => write!(f, "vertex"),
+            ShaderStage::Fragment => write!(f, "fragment"),
+        }
+    }
+}
+
+// A line in one of the files making up a preprocessed shader.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SourceLocation {
+    pub path: PathBuf,
+    pub line: u32,
+}
+
+impl fmt::Display for SourceLocation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.path.display(), self.line)
+    }
+}
+
+#[derive(Debug)]
+pub enum ShaderError {
+    Io {
+        path: PathBuf,
+        source: io::Error,
+    },
+    Preprocess {
+        location: SourceLocation,
+        message: String,
+    },
+    Compile {
+        stage: ShaderStage,
+        path: PathBuf,
+        // Where the driver reported errors, mapped back through the `#line` directives.
+        locations: Vec<SourceLocation>,
+        // Files in source string order, the log refers to them by index.
+        files: Vec<PathBuf>,
+        log: String,
+    },
+    Link {
+        vertex: PathBuf,
+        fragment: PathBuf,
+        log: String,
+    },
+    // A mesh does not provide the vertex inputs a program reads.
+    VertexLayout {
+        program: String,
+        message: String,
+    },
+}
+
+impl fmt::Display for ShaderError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ShaderError::Io { path, source } => {
+                write!(f, "could not read shader {}: {}", path.display(), source)
+            }
+            ShaderError::Preprocess { location, message } => {
+                write!(f, "{}: {}", location, message)
+            }
+            ShaderError::Compile {
+                stage,
+                path,
+                locations,
+                files,
+                log,
+            } => {
+                write!(f, "{} shader {} failed to compile", stage, path.display())?;
+                if !locations.is_empty() {
+                    let locations: Vec<String> = locations
+                        .iter()
+                        .map(|location| location.to_string())
+                        .collect();
+                    write!(f, " at {}", locations.join(", "))?;
+                }
+                writeln!(f, ":")?;
+                if files.len() > 1 {
+                    for (index, file) in files.iter().enumerate() {
+                        writeln!(f, "  source {} = {}", index, file.display())?;
+                    }
+                }
+                write!(f, "{}", log.trim_end())
+            }
+            ShaderError::Link {
+                vertex,
+                fragment,
+                log,
+            } => write!(
+                f,
+                "program {} + {} failed to link:\n{}",
+                vertex.display(),
+                fragment.display(),
+                log.trim_end()
+            ),
+            ShaderError::VertexLayout { program, message } => {
+                write!(f, "vertex layout does not match {}: {}", program, message)
+            }
+        }
+    }
+}
+
+impl Error for ShaderError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ShaderError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+// Drivers disagree on the log format: `1(12) : error ...` (NVIDIA), `1:12(5): error: ...`
+// (Mesa) and `ERROR: 1:12: ...` (AMD, Intel) all refer to line 12 of source string 1.
+pub fn parse_locations(log: &str) -> Vec<(u32, u32)> {
+    let mut locations = Vec::new();
+    for entry in log.lines() {
+        let entry = entry.trim_start();
+        let entry = entry
+            .strip_prefix("ERROR: ")
+            .or_else(|| entry.strip_prefix("WARNING: "))
+            .unwrap_or(entry);
+
+        let source_digits: String = entry.chars().take_while(|c| c.is_ascii_digit()).collect();
+        let Ok(source) = source_digits.parse() else {
+            continue;
+        };
+        let rest = &entry[source_digits.len()..];
+        let Some(rest) = rest.strip_prefix('(').or_else(|| rest.strip_prefix(':')) else {
+            continue;
+        };
+        let digits: String = rest.chars().take_while(|c| c.is_ascii_digit()).collect();
+        if let Ok(line) = digits.parse() {
+            if !locations.contains(&(source, line)) {
+                locations.push((source, line));
+            }
+        }
+    }
+    locations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_nvidia_logs() {
+        let log = "0(12) : error C1008: undefined variable \"x\"\n\
+                   2(4) : warning C7022: unrecognized profile specifier\n";
+        assert_eq!(parse_locations(log), [(0, 12), (2, 4)]);
+    }
+
+    #[test]
+    fn parses_mesa_logs() {
+        let log = "1:12(5): error: `x' undeclared\n1:12(9): error: type mismatch\n";
+        assert_eq!(parse_locations(log), [(1, 12)]);
+    }
+
+    #[test]
+    fn parses_amd_and_intel_logs() {
+        let log = "ERROR: 3:7: 'x' : undeclared identifier\n\
+                   WARNING: 0:2: extension not supported\n\
+                   ERROR: 2 compilation errors.  No code generated.\n";
+        assert_eq!(parse_locations(log), [(3, 7), (0, 2)]);
+    }
+
+    #[test]
+    fn ignores_lines_without_a_location() {
+        assert!(parse_locations("Compile failed.\n\n  error: out of memory\n").is_empty());
+    }
+}
@@ -0,0 +1,41 @@
+#version 330 core
+in vec2 ScreenPos;
+
+out vec4 FragColor;
+
+uniform sampler2D source;
+uniform vec2 texelSize;
+
+const float SPAN_MAX = 8.0;
+const float REDUCE_MUL = 1.0 / 8.0;
+const float REDUCE_MIN = 1.0 / 128.0;
+
+float luma(vec3 color) {
+    return dot(color, vec3(0.299, 0.587, 0.114));
+}
+
+// Blurs along the local edge direction estimated from the four diagonal neighbours.
+void main() {
+    vec2 uv = ScreenPos * 0.5 + 0.5;
+    vec3 rgbM = texture(source, uv).rgb;
+    float lumaNW = luma(texture(source, uv + vec2(-1.0, -1.0) * texelSize).rgb);
+    float lumaNE = luma(texture(source, uv + vec2(1.0, -1.0) * texelSize).rgb);
+    float lumaSW = luma(texture(source, uv + vec2(-1.0, 1.0) * texelSize).rgb);
+    float lumaSE = luma(texture(source, uv + vec2(1.0, 1.0) * texelSize).rgb);
+    float lumaM = luma(rgbM);
+    float lumaMin = min(lumaM, min(min(lumaNW, lumaNE), min(lumaSW, lumaSE)));
+    float lumaMax = max(lumaM, max(max(lumaNW, lumaNE), max(lumaSW, lumaSE)));
+
+    vec2 dir = vec2(-((lumaNW + lumaNE) - (lumaSW + lumaSE)), (lumaNW + lumaSW) - (lumaNE + lumaSE));
+    float dirReduce = max((lumaNW + lumaNE + lumaSW + lumaSE) * 0.25 * REDUCE_MUL, REDUCE_MIN);
+    float rcpDirMin = 1.0 / (min(abs(dir.x), abs(dir.y)) + dirReduce);
+    dir = clamp(dir * rcpDirMin, -SPAN_MAX, SPAN_MAX) * texelSize;
+
+    vec3 rgbA = 0.5 * (texture(source, uv + dir * (1.0 / 3.0 - 0.5)).rgb
+        + texture(source, uv + dir * (2.0 / 3.0 - 0.5)).rgb);
+    vec3 rgbB = rgbA * 0.5 + 0.25 * (texture(source, uv - dir * 0.5).rgb
+        + texture(source, uv + dir * 0.5).rgb);
+    float lumaB = luma(rgbB);
+
+    FragColor = vec4(lumaB < lumaMin || lumaB > lumaMax ? rgbA : rgbB, 1.0);
+}
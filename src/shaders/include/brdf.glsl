@@ -0,0 +1,29 @@
+#include "common.glsl"
+
+// Cook-Torrance: GGX distribution, Smith-Schlick geometry and Schlick Fresnel over a
+// Lambertian diffuse lobe.
+vec3 cookTorrance(vec3 n, vec3 toLight, vec3 viewDir, vec3 radiance, vec3 albedo,
+                  float roughness, float metalness) {
+    float nDotL = max(dot(n, toLight), 0.0);
+    if (nDotL <= 0.0) {
+        return vec3(0.0);
+    }
+    vec3 halfDir = normalize(toLight + viewDir);
+    float nDotV = max(dot(n, viewDir), 1e-4);
+    float nDotH = max(dot(n, halfDir), 0.0);
+
+    float alpha = max(roughness * roughness, 1e-3);
+    float alpha2 = alpha * alpha;
+    float d = nDotH * nDotH * (alpha2 - 1.0) + 1.0;
+    float distribution = alpha2 / (PI * d * d);
+
+    float k = (roughness + 1.0) * (roughness + 1.0) / 8.0;
+    float geometry = nDotL / (nDotL * (1.0 - k) + k) * nDotV / (nDotV * (1.0 - k) + k);
+
+    vec3 f0 = mix(vec3(0.04), albedo, metalness);
+    vec3 fresnel = f0 + (1.0 - f0) * pow(1.0 - max(dot(halfDir, viewDir), 0.0), 5.0);
+
+    vec3 specular = distribution * geometry * fresnel / (4.0 * nDotL * nDotV);
+    vec3 diffuse = (1.0 - fresnel) * (1.0 - metalness) * albedo / PI;
+    return (diffuse + specular) * radiance * nDotL;
+}
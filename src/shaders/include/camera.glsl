@@ -0,0 +1,9 @@
+// Per-frame camera data shared by every scene program, see scene::camera::CameraBuffer.
+layout (std140) uniform Camera {
+    mat4 view;
+    mat4 projection;
+    vec3 viewPos;
+    float logDepthCoef;
+    bool logDepth;
+    float logDepthScale;
+};
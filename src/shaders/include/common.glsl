@@ -0,0 +1,6 @@
+const float PI = 3.14159265359;
+
+// Longitude 0 faces +z and the north pole is +y, see scene::geo.
+vec2 globeUV(vec3 n) {
+    return vec2(atan(n.x, n.z) / (2.0 * PI) + 0.5, 0.5 - asin(clamp(n.y, -1.0, 1.0)) / PI);
+}
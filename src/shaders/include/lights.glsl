@@ -0,0 +1,40 @@
+// MAX_LIGHTS is injected from scene::light.
+#define DIRECTIONAL 0
+#define POINT 1
+#define SPOT 2
+
+// Mirrors scene::light::Light, see write_std140.
+struct LightData {
+    vec4 positionType;
+    vec4 directionInner;
+    vec4 colorIntensity;
+    vec4 attenuationOuter;
+};
+
+layout (std140) uniform Lights {
+    vec4 lightCount;
+    LightData lights[MAX_LIGHTS];
+};
+
+// Radiance reaching `pos` from a light, and the direction towards it.
+vec3 lightRadiance(LightData light, vec3 pos, out vec3 toLight) {
+    int type = int(light.positionType.w);
+    vec3 radiance = light.colorIntensity.rgb * light.colorIntensity.a;
+
+    if (type == DIRECTIONAL) {
+        toLight = normalize(light.directionInner.xyz);
+        return radiance;
+    }
+
+    vec3 offset = light.positionType.xyz - pos;
+    float dist = length(offset);
+    toLight = offset / dist;
+    vec3 att = light.attenuationOuter.xyz;
+    radiance /= att.x + att.y * dist + att.z * dist * dist;
+
+    if (type == SPOT) {
+        float theta = dot(-toLight, normalize(light.directionInner.xyz));
+        radiance *= smoothstep(light.attenuationOuter.w, light.directionInner.w, theta);
+    }
+    return radiance;
+}
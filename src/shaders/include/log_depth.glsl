@@ -0,0 +1,6 @@
+#include "camera.glsl"
+
+// Window depth for the fragment, see scene::depth.
+float fragmentDepth(float logZ) {
+    return logDepth ? log2(logZ) * logDepthCoef * 0.5 : gl_FragCoord.z;
+}
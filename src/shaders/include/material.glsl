@@ -0,0 +1,12 @@
+// Mirrors objects::material::Material, see Material::apply.
+struct Material {
+    vec3 baseColor;
+    bool hasBaseColorMap;
+    sampler2D baseColorMap;
+    float roughness;
+    float metalness;
+    vec3 emissive;
+    float opacity;
+};
+
+uniform Material material;
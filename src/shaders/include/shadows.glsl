@@ -0,0 +1,41 @@
+// CASCADES is injected from scene::shadows.
+#include "camera.glsl"
+
+uniform bool hasShadows;
+uniform bool receivesShadows;
+uniform sampler2DArrayShadow shadowMap;
+uniform mat4 shadowMatrices[CASCADES];
+uniform float cascadeSplits[CASCADES];
+uniform float shadowTexelSizes[CASCADES];
+
+// Fraction of sunlight reaching `pos`, from the cascade covering its view depth with
+// 3x3 PCF. See scene::shadows.
+float sunShadow(vec3 pos, vec3 n) {
+    if (!hasShadows || !receivesShadows) {
+        return 1.0;
+    }
+
+    float viewDepth = -(view * vec4(pos, 1.0)).z;
+    if (viewDepth > cascadeSplits[CASCADES - 1]) {
+        return 1.0;
+    }
+    int cascade = CASCADES - 1;
+    for (int i = 0; i < CASCADES; i++) {
+        if (viewDepth < cascadeSplits[i]) {
+            cascade = i;
+            break;
+        }
+    }
+
+    // Pushing the lookup along the normal avoids acne on surfaces facing away from the sun.
+    vec3 offsetPos = pos + n * shadowTexelSizes[cascade] * 1.5;
+    vec4 coord = shadowMatrices[cascade] * vec4(offsetPos, 1.0);
+    vec2 texel = 1.0 / vec2(textureSize(shadowMap, 0).xy);
+    float lit = 0.0;
+    for (int x = -1; x <= 1; x++) {
+        for (int y = -1; y <= 1; y++) {
+            lit += texture(shadowMap, vec4(coord.xy + vec2(x, y) * texel, float(cascade), coord.z));
+        }
+    }
+    return lit / 9.0;
+}
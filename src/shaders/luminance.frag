@@ -0,0 +1,17 @@
+#version 330 core
+in vec2 ScreenPos;
+
+out vec4 FragColor;
+
+uniform sampler2D hdrColor;
+
+// Darker pixels are left out of the average, see scene::hdr::LuminanceMeter.
+const float MIN_LUMINANCE = 1e-4;
+
+// The log luminance weighted by coverage, and the coverage, for the mips to average.
+void main() {
+    vec3 color = textureLod(hdrColor, ScreenPos * 0.5 + 0.5, 0.0).rgb;
+    float luminance = dot(color, vec3(0.2126, 0.7152, 0.0722));
+    float coverage = luminance >= MIN_LUMINANCE ? 1.0 : 0.0;
+    FragColor = vec4(log(max(luminance, MIN_LUMINANCE)) * coverage, coverage, 0.0, 1.0);
+}
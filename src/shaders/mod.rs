@@ -1,2 +1,8 @@
+pub mod cache;
 pub mod core;
+pub mod error;
 pub mod program;
+pub mod reflection;
+pub mod registry;
+pub mod uniform;
+pub mod uniform_buffer;
@@ -1,68 +1,249 @@
 use crate::algebra::mat4::Mat4;
 use crate::algebra::vec3::Vec3;
-use crate::shaders::core::load_shader;
-use std::ffi::CString;
-use std::ptr;
+use crate::render::vertex::Semantic;
+use crate::shaders::core::{load_shader, Program};
+use crate::shaders::error::{ShaderError, ShaderStage};
+use crate::shaders::reflection::{type_name, ProgramReflection};
+use crate::shaders::uniform::{Sampler, Uniform};
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 
 pub struct ShaderProgram {
-    pub id: gl::types::GLuint,
+    program: Program,
+    // Set while the sources on disk fail to build and the previous program stays in use.
+    pub stale: bool,
+    vertex_path: String,
+    fragment_path: String,
+    defines: Vec<(String, String)>,
+    // Every file the last build read, includes too. A failed build adds what it read to
+    // those of the last successful one.
+    sources: Vec<PathBuf>,
+    modified: Option<SystemTime>,
+    block_bindings: Vec<(String, u32)>,
+    reflection: ProgramReflection,
+    // Location and type of the active uniforms. Array elements are listed both as `name[i]`
+    // and, for the first one, as `name`.
+    uniforms: HashMap<String, (gl::types::GLint, gl::types::GLenum)>,
+    // Names already reported, so a bad name or type warns once rather than every frame.
+    warned: RefCell<HashSet<String>>,
 }
 
 impl ShaderProgram {
-    pub fn new(vertex_path: &str, fragment_path: &str) -> Self {
-        let program = unsafe {
-            let vertex_shader = load_shader(vertex_path, gl::VERTEX_SHADER);
-            let fragment_shader = load_shader(fragment_path, gl::FRAGMENT_SHADER);
-
-            let shader_program = gl::CreateProgram();
-            gl::AttachShader(shader_program, vertex_shader);
-            gl::AttachShader(shader_program, fragment_shader);
-            gl::LinkProgram(shader_program);
-
-            let mut success = gl::FALSE as gl::types::GLint;
-            let mut info_log = Vec::with_capacity(512 - 1);
-            gl::GetProgramiv(shader_program, gl::LINK_STATUS, &mut success);
-            if success != gl::TRUE as gl::types::GLint {
-                gl::GetProgramInfoLog(
-                    shader_program,
-                    512,
-                    ptr::null_mut(),
-                    info_log.as_mut_ptr() as *mut gl::types::GLchar,
-                );
-                println!(
-                    "ERROR::SHADER::PROGRAM::LINKING_FAILED\n{}",
-                    String::from_utf8_lossy(&info_log)
-                );
-            }
+    pub fn new(vertex_path: &str, fragment_path: &str) -> Result<Self, ShaderError> {
+        ShaderProgram::with_defines(vertex_path, fragment_path, &[])
+    }
 
-            gl::DeleteShader(vertex_shader);
-            gl::DeleteShader(fragment_shader);
+    // `defines` are injected into both stages, see shaders::core::preprocess.
+    pub fn with_defines(
+        vertex_path: &str,
+        fragment_path: &str,
+        defines: &[(String, String)],
+    ) -> Result<Self, ShaderError> {
+        let mut sources = Vec::new();
+        let program = link_program(vertex_path, fragment_path, defines, &mut sources)?;
+        let reflection = ProgramReflection::query(program.id());
+        Ok(ShaderProgram {
+            stale: false,
+            vertex_path: vertex_path.to_string(),
+            fragment_path: fragment_path.to_string(),
+            defines: defines.to_vec(),
+            modified: sources_modified(&sources),
+            sources,
+            block_bindings: Vec::new(),
+            uniforms: uniform_locations(&program, &reflection),
+            program,
+            reflection,
+            warned: RefCell::new(HashSet::new()),
+        })
+    }
 
-            shader_program
-        };
+    // Rebuilds the program when any of its sources changed on disk since the last attempt.
+    // On failure the current program is kept and the error returned once per change.
+    pub fn reload_if_changed(&mut self) -> Result<bool, ShaderError> {
+        let modified = sources_modified(&self.sources);
+        if modified == self.modified {
+            return Ok(false);
+        }
+        self.modified = modified;
 
-        ShaderProgram { id: program }
+        let mut sources = Vec::new();
+        match link_program(
+            &self.vertex_path,
+            &self.fragment_path,
+            &self.defines,
+            &mut sources,
+        ) {
+            Ok(program) => {
+                self.stale = false;
+                self.reflection = ProgramReflection::query(program.id());
+                self.uniforms = uniform_locations(&program, &self.reflection);
+                self.program = program;
+                self.warned.borrow_mut().clear();
+                self.modified = sources_modified(&sources);
+                self.sources = sources;
+                for (name, binding) in self.block_bindings.clone() {
+                    self.bind_uniform_block(&name, binding);
+                }
+                Ok(true)
+            }
+            Err(err) => {
+                // Watch files the attempt reached for the first time too, e.g. a new include
+                // holding the error. Files deleted since are dropped so the rest still count.
+                self.sources.retain(|path| path.exists());
+                for path in sources {
+                    if !self.sources.contains(&path) {
+                        self.sources.push(path);
+                    }
+                }
+                self.modified = sources_modified(&self.sources);
+                self.stale = true;
+                Err(err)
+            }
+        }
     }
 
     pub fn use_program(&self) {
-        unsafe {
-            gl::UseProgram(self.id);
+        self.program.use_program();
+    }
+
+    // Remembered so the binding survives a reload.
+    pub fn bind_uniform_block(&mut self, name: &str, binding: u32) {
+        if !self.block_bindings.iter().any(|(bound, _)| bound == name) {
+            self.block_bindings.push((name.to_string(), binding));
+        }
+        if let Some(block) = self
+            .reflection
+            .blocks
+            .iter()
+            .find(|block| block.name == name)
+        {
+            self.program.bind_uniform_block(block.index, binding);
         }
     }
 
-    pub fn set_mat4(&self, name: &str, value: &Mat4) {
-        unsafe {
-            let c_name = CString::new(name).unwrap();
-            let location = gl::GetUniformLocation(self.id, c_name.as_ptr());
-            gl::UniformMatrix4fv(location, 1, gl::FALSE, value.as_ptr());
+    pub fn reflection(&self) -> &ProgramReflection {
+        &self.reflection
+    }
+
+    // Source names, for messages.
+    pub fn label(&self) -> String {
+        format!("{} + {}", self.vertex_path, self.fragment_path)
+    }
+
+    pub fn set<T: Uniform + ?Sized>(&self, name: &str, value: &T) {
+        match self.uniforms.get(name) {
+            Some(&(location, kind)) if T::accepts(kind) => value.upload(location),
+            Some(&(_, kind)) => {
+                self.warn_once(name, &format!("uniform {} is a {}", name, type_name(kind)))
+            }
+            None => match self.reflection.block_containing(name) {
+                Some(block) => self.warn_once(
+                    name,
+                    &format!(
+                        "uniform {} is in block {}, set it through its buffer",
+                        name, block.name
+                    ),
+                ),
+                None => self.warn_once(name, &format!("no active uniform {}", name)),
+            },
         }
     }
 
+    pub fn set_mat4(&self, name: &str, value: &Mat4) {
+        self.set(name, value);
+    }
+
+    pub fn set_float(&self, name: &str, value: f32) {
+        self.set(name, &value);
+    }
+
+    pub fn set_int(&self, name: &str, value: i32) {
+        self.set(name, &value);
+    }
+
+    pub fn set_bool(&self, name: &str, value: bool) {
+        self.set(name, &value);
+    }
+
+    pub fn set_vec2(&self, name: &str, x: f32, y: f32) {
+        self.set(name, &[x, y]);
+    }
+
     pub fn set_vec3(&self, name: &str, value: &Vec3) {
-        unsafe {
-            let c_name = CString::new(name).unwrap();
-            let location = gl::GetUniformLocation(self.id, c_name.as_ptr());
-            gl::Uniform3f(location, value.x, value.y, value.z);
+        self.set(name, value);
+    }
+
+    pub fn set_sampler(&self, name: &str, unit: u32) {
+        self.set(name, &Sampler(unit));
+    }
+
+    fn warn_once(&self, name: &str, message: &str) {
+        if self.warned.borrow_mut().insert(name.to_string()) {
+            eprintln!("warning: {}: {}", self.label(), message);
+        }
+    }
+}
+
+fn uniform_locations(
+    program: &Program,
+    reflection: &ProgramReflection,
+) -> HashMap<String, (gl::types::GLint, gl::types::GLenum)> {
+    let mut uniforms = HashMap::new();
+    for uniform in &reflection.uniforms {
+        if uniform.location < 0 {
+            continue;
+        }
+        let Some(base) = uniform.name.strip_suffix("[0]") else {
+            uniforms.insert(uniform.name.clone(), (uniform.location, uniform.kind));
+            continue;
+        };
+        uniforms.insert(base.to_string(), (uniform.location, uniform.kind));
+        for element in 0..uniform.size {
+            let element_name = format!("{}[{}]", base, element);
+            let location = program.uniform_location(&element_name);
+            uniforms.insert(element_name, (location, uniform.kind));
         }
     }
+    uniforms
+}
+
+// The linked program. The files both stages were built from are added to `sources`, as far
+// as the build got.
+fn link_program(
+    vertex_path: &str,
+    fragment_path: &str,
+    defines: &[(String, String)],
+    sources: &mut Vec<PathBuf>,
+) -> Result<Program, ShaderError> {
+    let vertex_shader = load_shader(vertex_path, ShaderStage::Vertex, defines, sources)?;
+    let fragment_shader = load_shader(fragment_path, ShaderStage::Fragment, defines, sources)?;
+
+    // Every program agrees on where each kind of vertex data goes, see render::vertex.
+    let attributes: Vec<_> = Semantic::ALL
+        .iter()
+        .map(|semantic| (semantic.attribute_name(), semantic.location()))
+        .collect();
+    let program =
+        Program::link(&[&vertex_shader, &fragment_shader], &attributes).map_err(|log| {
+            ShaderError::Link {
+                vertex: PathBuf::from(vertex_path),
+                fragment: PathBuf::from(fragment_path),
+                log,
+            }
+        })?;
+    Ok(program)
+}
+
+// Latest modification time of the sources, `None` if any of them cannot be read.
+fn sources_modified(sources: &[PathBuf]) -> Option<SystemTime> {
+    let modified = |path: &Path| fs::metadata(path).and_then(|meta| meta.modified()).ok();
+    sources
+        .iter()
+        .map(|path| modified(path))
+        .try_fold(SystemTime::UNIX_EPOCH, |latest, time| {
+            Some(latest.max(time?))
+        })
 }
@@ -0,0 +1,246 @@
+use gl::types::{GLenum, GLint, GLuint};
+
+use std::ffi::CString;
+
+// An active vertex input or uniform as the driver reports it.
+#[derive(Clone, Debug)]
+pub struct Variable {
+    pub name: String,
+    // Attribute or uniform location, -1 for members of uniform blocks.
+    pub location: GLint,
+    // GL type enum, e.g. gl::FLOAT_VEC3.
+    pub kind: GLenum,
+    // Array length, 1 for single values.
+    pub size: GLint,
+}
+
+#[derive(Clone, Debug)]
+pub struct UniformBlock {
+    pub name: String,
+    pub index: GLuint,
+    pub members: Vec<String>,
+}
+
+// What a linked program expects from its inputs, queried once per link.
+#[derive(Clone, Debug, Default)]
+pub struct ProgramReflection {
+    pub attributes: Vec<Variable>,
+    pub uniforms: Vec<Variable>,
+    pub blocks: Vec<UniformBlock>,
+}
+
+impl ProgramReflection {
+    pub fn query(program: GLuint) -> Self {
+        let mut blocks = uniform_blocks(program);
+        let mut uniforms = Vec::new();
+        for (variable, block) in active_variables(program, Interface::Uniform) {
+            match block {
+                Some(index) => {
+                    if let Some(block) = blocks.iter_mut().find(|block| block.index == index) {
+                        block.members.push(variable.name);
+                    }
+                }
+                None => uniforms.push(variable),
+            }
+        }
+
+        ProgramReflection {
+            attributes: active_variables(program, Interface::Attribute)
+                .into_iter()
+                .map(|(variable, _)| variable)
+                .collect(),
+            uniforms,
+            blocks,
+        }
+    }
+
+    pub fn block_containing(&self, member: &str) -> Option<&UniformBlock> {
+        self.blocks
+            .iter()
+            .find(|block| block.members.iter().any(|name| name == member))
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Interface {
+    Attribute,
+    Uniform,
+}
+
+// Each variable with the index of its uniform block, if it is in one.
+fn active_variables(program: GLuint, interface: Interface) -> Vec<(Variable, Option<GLuint>)> {
+    let (count_query, length_query) = match interface {
+        Interface::Attribute => (gl::ACTIVE_ATTRIBUTES, gl::ACTIVE_ATTRIBUTE_MAX_LENGTH),
+        Interface::Uniform => (gl::ACTIVE_UNIFORMS, gl::ACTIVE_UNIFORM_MAX_LENGTH),
+    };
+
+    let mut variables = Vec::new();
+    unsafe {
+        let (mut count, mut max_length) = (0, 0);
+        gl::GetProgramiv(program, count_query, &mut count);
+        gl::GetProgramiv(program, length_query, &mut max_length);
+
+        for index in 0..count as GLuint {
+            let mut buffer = vec![0u8; max_length.max(1) as usize];
+            let (mut length, mut size, mut kind) = (0, 0, 0);
+            let get_active = match interface {
+                Interface::Attribute => gl::GetActiveAttrib,
+                Interface::Uniform => gl::GetActiveUniform,
+            };
+            get_active(
+                program,
+                index,
+                max_length,
+                &mut length,
+                &mut size,
+                &mut kind,
+                buffer.as_mut_ptr() as *mut gl::types::GLchar,
+            );
+            let name = String::from_utf8_lossy(&buffer[..length as usize]).into_owned();
+
+            let c_name = CString::new(name.as_str()).unwrap();
+            let (location, block) = match interface {
+                Interface::Attribute => (gl::GetAttribLocation(program, c_name.as_ptr()), None),
+                Interface::Uniform => {
+                    let mut block_index = -1;
+                    gl::GetActiveUniformsiv(
+                        program,
+                        1,
+                        &index,
+                        gl::UNIFORM_BLOCK_INDEX,
+                        &mut block_index,
+                    );
+                    (
+                        gl::GetUniformLocation(program, c_name.as_ptr()),
+                        (block_index >= 0).then_some(block_index as GLuint),
+                    )
+                }
+            };
+
+            variables.push((
+                Variable {
+                    name,
+                    location,
+                    kind,
+                    size,
+                },
+                block,
+            ));
+        }
+    }
+    variables
+}
+
+fn uniform_blocks(program: GLuint) -> Vec<UniformBlock> {
+    let mut blocks = Vec::new();
+    unsafe {
+        let (mut count, mut max_length) = (0, 0);
+        gl::GetProgramiv(program, gl::ACTIVE_UNIFORM_BLOCKS, &mut count);
+        gl::GetProgramiv(
+            program,
+            gl::ACTIVE_UNIFORM_BLOCK_MAX_NAME_LENGTH,
+            &mut max_length,
+        );
+
+        for index in 0..count as GLuint {
+            let mut buffer = vec![0u8; max_length.max(1) as usize];
+            let mut length = 0;
+            gl::GetActiveUniformBlockName(
+                program,
+                index,
+                max_length,
+                &mut length,
+                buffer.as_mut_ptr() as *mut gl::types::GLchar,
+            );
+            blocks.push(UniformBlock {
+                name: String::from_utf8_lossy(&buffer[..length as usize]).into_owned(),
+                index,
+                members: Vec::new(),
+            });
+        }
+    }
+    blocks
+}
+
+// Component count and scalar type of an attribute type, e.g. (3, gl::FLOAT) for vec3.
+pub fn vector_shape(kind: GLenum) -> Option<(i32, GLenum)> {
+    Some(match kind {
+        gl::FLOAT => (1, gl::FLOAT),
+        gl::FLOAT_VEC2 => (2, gl::FLOAT),
+        gl::FLOAT_VEC3 => (3, gl::FLOAT),
+        gl::FLOAT_VEC4 => (4, gl::FLOAT),
+        gl::INT => (1, gl::INT),
+        gl::INT_VEC2 => (2, gl::INT),
+        gl::INT_VEC3 => (3, gl::INT),
+        gl::INT_VEC4 => (4, gl::INT),
+        gl::UNSIGNED_INT => (1, gl::UNSIGNED_INT),
+        gl::UNSIGNED_INT_VEC2 => (2, gl::UNSIGNED_INT),
+        gl::UNSIGNED_INT_VEC3 => (3, gl::UNSIGNED_INT),
+        gl::UNSIGNED_INT_VEC4 => (4, gl::UNSIGNED_INT),
+        gl::BOOL => (1, gl::BOOL),
+        gl::BOOL_VEC2 => (2, gl::BOOL),
+        gl::BOOL_VEC3 => (3, gl::BOOL),
+        gl::BOOL_VEC4 => (4, gl::BOOL),
+        _ => return None,
+    })
+}
+
+pub fn is_sampler(kind: GLenum) -> bool {
+    matches!(
+        kind,
+        gl::SAMPLER_1D
+            | gl::SAMPLER_2D
+            | gl::SAMPLER_3D
+            | gl::SAMPLER_CUBE
+            | gl::SAMPLER_1D_SHADOW
+            | gl::SAMPLER_2D_SHADOW
+            | gl::SAMPLER_1D_ARRAY
+            | gl::SAMPLER_2D_ARRAY
+            | gl::SAMPLER_1D_ARRAY_SHADOW
+            | gl::SAMPLER_2D_ARRAY_SHADOW
+            | gl::SAMPLER_CUBE_SHADOW
+            | gl::SAMPLER_2D_MULTISAMPLE
+            | gl::SAMPLER_2D_MULTISAMPLE_ARRAY
+            | gl::SAMPLER_BUFFER
+            | gl::SAMPLER_2D_RECT
+            | gl::SAMPLER_2D_RECT_SHADOW
+            | gl::INT_SAMPLER_2D
+            | gl::INT_SAMPLER_3D
+            | gl::INT_SAMPLER_2D_ARRAY
+            | gl::UNSIGNED_INT_SAMPLER_2D
+            | gl::UNSIGNED_INT_SAMPLER_3D
+            | gl::UNSIGNED_INT_SAMPLER_2D_ARRAY
+    )
+}
+
+// GLSL spelling of a type, for messages.
+pub fn type_name(kind: GLenum) -> String {
+    match kind {
+        gl::FLOAT_MAT2 => "mat2".to_string(),
+        gl::FLOAT_MAT3 => "mat3".to_string(),
+        gl::FLOAT_MAT4 => "mat4".to_string(),
+        kind if is_sampler(kind) => "sampler".to_string(),
+        kind => match vector_shape(kind) {
+            Some((1, scalar)) => scalar_name(scalar).to_string(),
+            Some((components, scalar)) => {
+                let prefix = match scalar {
+                    gl::INT => "i",
+                    gl::UNSIGNED_INT => "u",
+                    gl::BOOL => "b",
+                    _ => "",
+                };
+                format!("{}vec{}", prefix, components)
+            }
+            None => format!("type 0x{:x}", kind),
+        },
+    }
+}
+
+fn scalar_name(scalar: GLenum) -> &'static str {
+    match scalar {
+        gl::INT => "int",
+        gl::UNSIGNED_INT => "uint",
+        gl::BOOL => "bool",
+        _ => "float",
+    }
+}
@@ -0,0 +1,157 @@
+use crate::shaders::error::ShaderError;
+
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+// Directory whose files replace the embedded shaders of the same name.
+pub const SHADER_DIR_VAR: &str = "EARTH_GL_SHADER_DIR";
+
+// Every shader and include, by name relative to src/shaders, so the binary runs from any
+// directory.
+const EMBEDDED: &[(&str, &str)] = &[
+    ("clouds.vert", include_str!("clouds.vert")),
+    ("fullscreen.vert", include_str!("fullscreen.vert")),
+    ("shader.vert", include_str!("shader.vert")),
+    ("shadow.vert", include_str!("shadow.vert")),
+    ("stars.vert", include_str!("stars.vert")),
+    ("atmosphere.frag", include_str!("atmosphere.frag")),
+    ("bloom_blur.frag", include_str!("bloom_blur.frag")),
+    ("bloom_bright.frag", include_str!("bloom_bright.frag")),
+    ("bloom_composite.frag", include_str!("bloom_composite.frag")),
+    ("clouds.frag", include_str!("clouds.frag")),
+    ("color_grading.frag", include_str!("color_grading.frag")),
+    ("fxaa.frag", include_str!("fxaa.frag")),
+    ("luminance.frag", include_str!("luminance.frag")),
+    ("shadow.frag", include_str!("shadow.frag")),
+    ("sharpen.frag", include_str!("sharpen.frag")),
+    ("stars.frag", include_str!("stars.frag")),
+    ("surface.frag", include_str!("surface.frag")),
+    ("tonemap.frag", include_str!("tonemap.frag")),
+    ("vignette.frag", include_str!("vignette.frag")),
+    ("include/brdf.glsl", include_str!("include/brdf.glsl")),
+    ("include/camera.glsl", include_str!("include/camera.glsl")),
+    ("include/common.glsl", include_str!("include/common.glsl")),
+    ("include/lights.glsl", include_str!("include/lights.glsl")),
+    (
+        "include/log_depth.glsl",
+        include_str!("include/log_depth.glsl"),
+    ),
+    (
+        "include/material.glsl",
+        include_str!("include/material.glsl"),
+    ),
+    ("include/shadows.glsl", include_str!("include/shadows.glsl")),
+];
+
+pub struct ShaderFile {
+    pub text: String,
+    // Set when the source came from the override directory.
+    pub path: Option<PathBuf>,
+}
+
+impl ShaderFile {
+    // Where errors in this file are reported: the file on disk, or the embedded name.
+    pub fn display_path(&self, name: &str) -> PathBuf {
+        self.path.clone().unwrap_or_else(|| PathBuf::from(name))
+    }
+}
+
+// The directory named by `EARTH_GL_SHADER_DIR`. Debug builds fall back to the crate's own
+// sources when they are still there, so edits reload without a rebuild.
+pub fn override_dir() -> Option<PathBuf> {
+    if let Some(dir) = env::var_os(SHADER_DIR_VAR) {
+        return Some(PathBuf::from(dir));
+    }
+    if cfg!(debug_assertions) {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/shaders");
+        if dir.is_dir() {
+            return Some(dir);
+        }
+    }
+    None
+}
+
+// Reads `name` from the override directory if it has it, otherwise from the binary.
+pub fn load(name: &str) -> Result<ShaderFile, ShaderError> {
+    if let Some(path) = override_dir().map(|dir| dir.join(name)) {
+        if path.is_file() {
+            return match fs::read_to_string(&path) {
+                Ok(text) => Ok(ShaderFile {
+                    text,
+                    path: Some(path),
+                }),
+                Err(source) => Err(ShaderError::Io { path, source }),
+            };
+        }
+    }
+
+    EMBEDDED
+        .iter()
+        .find(|(embedded, _)| *embedded == name)
+        .map(|(_, text)| ShaderFile {
+            text: text.to_string(),
+            path: None,
+        })
+        .ok_or_else(|| ShaderError::Io {
+            path: PathBuf::from(name),
+            source: io::Error::new(io::ErrorKind::NotFound, "no such shader"),
+        })
+}
+
+// Name of `include` as written in shader `name`, e.g. "common.glsl" included from
+// "include/brdf.glsl" is "include/common.glsl".
+pub fn resolve(name: &str, include: &str) -> String {
+    let mut parts: Vec<&str> = name.split('/').collect();
+    parts.pop();
+    for part in include.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            part => parts.push(part),
+        }
+    }
+    parts.join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Shader files under `dir`, by name relative to src/shaders.
+    fn shader_files(dir: &Path, prefix: &str, names: &mut Vec<String>) {
+        for entry in fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            let file_name = path.file_name().unwrap().to_str().unwrap();
+            let name = format!("{}{}", prefix, file_name);
+            if path.is_dir() {
+                shader_files(&path, &format!("{}/", name), names);
+            } else if matches!(
+                path.extension().and_then(|ext| ext.to_str()),
+                Some("vert" | "frag" | "glsl")
+            ) {
+                names.push(name);
+            }
+        }
+    }
+
+    #[test]
+    fn every_shader_is_embedded() {
+        let mut names = Vec::new();
+        shader_files(
+            &Path::new(env!("CARGO_MANIFEST_DIR")).join("src/shaders"),
+            "",
+            &mut names,
+        );
+        assert!(names.iter().any(|name| name.starts_with("include/")));
+
+        let missing: Vec<_> = names
+            .iter()
+            .filter(|name| !EMBEDDED.iter().any(|(embedded, _)| embedded == name))
+            .collect();
+        assert!(missing.is_empty(), "not embedded: {:?}", missing);
+    }
+}
@@ -1,20 +1,19 @@
 #version 330 core
-layout (location = 0) in vec3 aPos;
-layout (location = 1) in vec3 aNormal;
+in vec3 aPos;
+in vec3 aNormal;
 
 out vec3 FragPos;
 out vec3 Normal;
-out vec3 Color;
+out float LogZ;
 
 uniform mat4 model;
-uniform mat4 view;
-uniform mat4 projection;
+
+#include "include/camera.glsl"
 
 void main() {
     FragPos = vec3(model * vec4(aPos, 1.0));
     Normal = mat3(transpose(inverse(model))) * aNormal;
 
-    Color = 0.5 + 0.5 * cos(aPos.xyx + vec3(0, 2, 4));
-
     gl_Position = projection * view * vec4(FragPos, 1.0);
+    LogZ = 1.0 + logDepthScale * gl_Position.w;
 }
\ No newline at end of file
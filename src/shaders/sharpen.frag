@@ -0,0 +1,20 @@
+#version 330 core
+in vec2 ScreenPos;
+
+out vec4 FragColor;
+
+uniform sampler2D source;
+uniform vec2 texelSize;
+uniform float strength;
+
+// Unsharp mask against the four direct neighbours.
+void main() {
+    vec2 uv = ScreenPos * 0.5 + 0.5;
+    vec3 center = texture(source, uv).rgb;
+    vec3 neighbours = texture(source, uv + vec2(texelSize.x, 0.0)).rgb
+        + texture(source, uv - vec2(texelSize.x, 0.0)).rgb
+        + texture(source, uv + vec2(0.0, texelSize.y)).rgb
+        + texture(source, uv - vec2(0.0, texelSize.y)).rgb;
+    vec3 color = center + strength * (4.0 * center - neighbours);
+    FragColor = vec4(clamp(color, 0.0, 1.0), 1.0);
+}
@@ -0,0 +1,9 @@
+#version 330 core
+in vec3 Color;
+
+out vec4 FragColor;
+
+void main() {
+    float falloff = 1.0 - smoothstep(0.0, 0.5, length(gl_PointCoord - vec2(0.5)));
+    FragColor = vec4(Color * falloff, 1.0);
+}
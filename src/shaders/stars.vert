@@ -0,0 +1,19 @@
+#version 330 core
+in vec3 aDirection;
+in vec3 aColor;
+in float aSize;
+
+out vec3 Color;
+
+uniform mat4 model;
+
+#include "include/camera.glsl"
+
+void main() {
+    // Stars sit at infinity: ignore the camera translation and keep them inside the clip volume.
+    vec3 viewDir = mat3(view) * mat3(model) * aDirection;
+    vec4 clip = projection * vec4(viewDir, 0.0);
+    gl_Position = vec4(clip.xy, 0.0, clip.w);
+    gl_PointSize = aSize;
+    Color = aColor;
+}
@@ -0,0 +1,137 @@
+#version 330 core
+// Lit surfaces. The GLOBE variant adds the Earth's water, night lights and cloud shadows.
+in vec3 FragPos;
+in vec3 Normal;
+in float LogZ;
+
+out vec4 FragColor;
+
+#include "include/common.glsl"
+#include "include/camera.glsl"
+#include "include/log_depth.glsl"
+#include "include/material.glsl"
+#include "include/lights.glsl"
+#include "include/shadows.glsl"
+#include "include/brdf.glsl"
+
+#ifdef GLOBE
+uniform bool hasNightLights;
+uniform sampler2D nightLights;
+uniform bool hasWaterMask;
+uniform sampler2D waterMask;
+uniform bool hasWaterNormals;
+uniform sampler2D waterNormals;
+uniform float time;
+uniform bool hasClouds;
+uniform sampler2D clouds;
+uniform float cloudRadius;
+uniform float cloudRotation;
+uniform float cloudShadowStrength;
+
+const vec3 WATER_COLOR = vec3(0.0, 0.04, 0.1);
+const float WATER_ROUGHNESS = 0.12;
+const float WATER_NORMAL_TILING = 200.0;
+const float WATER_NORMAL_STRENGTH = 0.15;
+
+// Looks up the cloud cover where the ray towards the sun leaves the cloud shell.
+float cloudShadow(vec3 pos, vec3 sunDir) {
+    if (!hasClouds || length(pos) > cloudRadius) {
+        return 1.0;
+    }
+
+    float b = dot(pos, sunDir);
+    float c = dot(pos, pos) - cloudRadius * cloudRadius;
+    vec3 hit = normalize(pos + sunDir * (-b + sqrt(max(b * b - c, 0.0))));
+
+    // Undo the shell rotation around +y.
+    float s = sin(-cloudRotation);
+    float co = cos(-cloudRotation);
+    vec3 local = vec3(co * hit.x + s * hit.z, hit.y, -s * hit.x + co * hit.z);
+    return 1.0 - cloudShadowStrength * texture(clouds, globeUV(local)).r;
+}
+
+vec3 waterNormal(vec3 n, vec2 uv) {
+    if (!hasWaterNormals) {
+        return n;
+    }
+
+    // Two normal map samples scrolling in different directions, in the local east/north/up frame.
+    vec3 east = normalize(cross(vec3(0.0, 1.0, 0.0), n) + vec3(1e-6, 0.0, 0.0));
+    vec3 north = cross(n, east);
+    vec2 tiled = uv * vec2(2.0, 1.0) * WATER_NORMAL_TILING;
+    vec3 a = texture(waterNormals, tiled + vec2(time * 0.02, time * 0.01)).xyz * 2.0 - 1.0;
+    vec3 b = texture(waterNormals, tiled * 0.7 - vec2(time * 0.015, -time * 0.02)).xyz * 2.0 - 1.0;
+    vec2 slope = (a.xy + b.xy) * 0.5 * WATER_NORMAL_STRENGTH;
+    return normalize(n + east * slope.x + north * slope.y);
+}
+
+// Fresnel-weighted GGX glint over a dark diffuse water body.
+vec3 shadeWater(vec3 n, vec3 toLight, vec3 radiance, vec3 viewDir) {
+    vec3 halfDir = normalize(toLight + viewDir);
+    float nDotL = max(dot(n, toLight), 0.0);
+    float nDotV = max(dot(n, viewDir), 0.1);
+    float nDotH = max(dot(n, halfDir), 0.0);
+
+    float fresnel = 0.02 + 0.98 * pow(1.0 - max(dot(halfDir, viewDir), 0.0), 5.0);
+    float alpha2 = pow(WATER_ROUGHNESS, 4.0);
+    float d = nDotH * nDotH * (alpha2 - 1.0) + 1.0;
+    float distribution = alpha2 / (PI * d * d);
+    vec3 glint = radiance * fresnel * distribution * nDotL / (4.0 * nDotV);
+
+    return WATER_COLOR / PI * radiance * nDotL + glint;
+}
+#endif
+
+void main() {
+    vec3 norm = normalize(Normal);
+    vec3 viewDir = normalize(viewPos - FragPos);
+    vec2 uv = globeUV(norm);
+
+    vec3 albedo = material.baseColor;
+    if (material.hasBaseColorMap) {
+        albedo *= texture(material.baseColorMap, uv).rgb;
+    }
+
+#ifdef GLOBE
+    float water = hasWaterMask ? texture(waterMask, uv).r : 0.0;
+    vec3 waterNorm = water > 0.0 ? waterNormal(norm, uv) : norm;
+#endif
+
+    vec3 result = 0.03 * albedo;
+    // The sun is always the first light, see Scene::render.
+    float shadow = sunShadow(FragPos, norm);
+    int count = min(int(lightCount.x), MAX_LIGHTS);
+    for (int i = 0; i < count; i++) {
+        vec3 toLight;
+        vec3 radiance = lightRadiance(lights[i], FragPos, toLight);
+        if (i == 0) {
+            radiance *= shadow;
+        }
+#ifdef GLOBE
+        if (int(lights[i].positionType.w) == DIRECTIONAL) {
+            radiance *= cloudShadow(FragPos, toLight);
+        }
+#endif
+        vec3 lit = cookTorrance(norm, toLight, viewDir, radiance, albedo,
+                                material.roughness, material.metalness);
+#ifdef GLOBE
+        if (water > 0.0) {
+            lit = mix(lit, shadeWater(waterNorm, toLight, radiance, viewDir), water);
+        }
+#endif
+        result += lit;
+    }
+
+#ifdef GLOBE
+    if (hasNightLights) {
+        vec3 sunDir = normalize(lights[0].directionInner.xyz);
+        float night = 1.0 - smoothstep(-0.1, 0.1, dot(norm, sunDir));
+        result += night * texture(nightLights, uv).rgb;
+    }
+#endif
+
+    result += material.emissive;
+    FragColor = vec4(result, material.opacity);
+
+    gl_FragDepth = fragmentDepth(LogZ);
+}
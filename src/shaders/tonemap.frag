@@ -0,0 +1,70 @@
+#version 330 core
+in vec2 ScreenPos;
+
+out vec4 FragColor;
+
+uniform sampler2D hdrColor;
+uniform float exposure;
+uniform int toneMapper;
+
+#define REINHARD 0
+#define ACES 1
+#define AGX 2
+
+vec3 reinhard(vec3 color) {
+    float luminance = dot(color, vec3(0.2126, 0.7152, 0.0722));
+    return color / (1.0 + luminance);
+}
+
+// Narkowicz's fit of the ACES filmic curve.
+vec3 aces(vec3 color) {
+    color *= 0.6;
+    return clamp((color * (2.51 * color + 0.03)) / (color * (2.43 * color + 0.59) + 0.14), 0.0, 1.0);
+}
+
+// Polynomial fit of the AgX base contrast curve, after Benjamin Wrensch.
+vec3 agxContrast(vec3 x) {
+    vec3 x2 = x * x;
+    vec3 x4 = x2 * x2;
+    return 15.5 * x4 * x2 - 40.14 * x4 * x + 31.96 * x4
+        - 6.868 * x2 * x + 0.4298 * x2 + 0.1191 * x - 0.00232;
+}
+
+// Returns display-encoded values, no further sRGB transfer is applied.
+vec3 agx(vec3 color) {
+    const mat3 inset = mat3(
+        0.842479062253094, 0.0423282422610123, 0.0423756549057051,
+        0.0784335999999992, 0.878468636469772, 0.0784336,
+        0.0792237451477643, 0.0791661274605434, 0.879142973793104);
+    const mat3 outset = mat3(
+        1.19687900512017, -0.0528968517574562, -0.0529716355144438,
+        -0.0980208811401368, 1.15190312990417, -0.0980434501171241,
+        -0.0990297440797205, -0.0989611768448433, 1.15107367264116);
+    const float minEv = -12.47393;
+    const float maxEv = 4.026069;
+
+    color = inset * color;
+    color = clamp(log2(max(color, vec3(1e-10))), minEv, maxEv);
+    color = (color - minEv) / (maxEv - minEv);
+    return clamp(outset * agxContrast(color), 0.0, 1.0);
+}
+
+vec3 linearToSrgb(vec3 color) {
+    vec3 low = color * 12.92;
+    vec3 high = 1.055 * pow(color, vec3(1.0 / 2.4)) - 0.055;
+    return mix(low, high, step(vec3(0.0031308), color));
+}
+
+void main() {
+    vec3 color = textureLod(hdrColor, ScreenPos * 0.5 + 0.5, 0.0).rgb * exposure;
+
+    if (toneMapper == AGX) {
+        color = agx(color);
+    } else if (toneMapper == ACES) {
+        color = linearToSrgb(aces(color));
+    } else {
+        color = linearToSrgb(reinhard(color));
+    }
+
+    FragColor = vec4(color, 1.0);
+}
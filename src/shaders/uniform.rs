@@ -0,0 +1,220 @@
+use crate::algebra::mat4::Mat4;
+use crate::algebra::vec3::Vec3;
+use crate::shaders::reflection::is_sampler;
+
+use gl::types::{GLenum, GLint};
+
+// A value that can be written to a uniform of the matching GLSL type. The program must be
+// in use.
+pub trait Uniform {
+    // Whether the value can be written to a uniform of GL type `kind`.
+    fn accepts(kind: GLenum) -> bool;
+
+    fn upload(&self, location: GLint);
+}
+
+// Texture unit for a `sampler*` uniform.
+#[derive(Clone, Copy)]
+pub struct Sampler(pub u32);
+
+impl Uniform for f32 {
+    fn accepts(kind: GLenum) -> bool {
+        kind == gl::FLOAT || kind == gl::BOOL
+    }
+
+    fn upload(&self, location: GLint) {
+        unsafe { gl::Uniform1f(location, *self) }
+    }
+}
+
+impl Uniform for i32 {
+    fn accepts(kind: GLenum) -> bool {
+        kind == gl::INT || kind == gl::BOOL || is_sampler(kind)
+    }
+
+    fn upload(&self, location: GLint) {
+        unsafe { gl::Uniform1i(location, *self) }
+    }
+}
+
+impl Uniform for u32 {
+    fn accepts(kind: GLenum) -> bool {
+        kind == gl::UNSIGNED_INT || kind == gl::BOOL
+    }
+
+    fn upload(&self, location: GLint) {
+        unsafe { gl::Uniform1ui(location, *self) }
+    }
+}
+
+impl Uniform for bool {
+    fn accepts(kind: GLenum) -> bool {
+        kind == gl::BOOL
+    }
+
+    fn upload(&self, location: GLint) {
+        unsafe { gl::Uniform1i(location, *self as i32) }
+    }
+}
+
+impl Uniform for Sampler {
+    fn accepts(kind: GLenum) -> bool {
+        is_sampler(kind)
+    }
+
+    fn upload(&self, location: GLint) {
+        unsafe { gl::Uniform1i(location, self.0 as i32) }
+    }
+}
+
+impl Uniform for [f32; 2] {
+    fn accepts(kind: GLenum) -> bool {
+        kind == gl::FLOAT_VEC2
+    }
+
+    fn upload(&self, location: GLint) {
+        unsafe { gl::Uniform2f(location, self[0], self[1]) }
+    }
+}
+
+impl Uniform for [f32; 3] {
+    fn accepts(kind: GLenum) -> bool {
+        kind == gl::FLOAT_VEC3
+    }
+
+    fn upload(&self, location: GLint) {
+        unsafe { gl::Uniform3f(location, self[0], self[1], self[2]) }
+    }
+}
+
+impl Uniform for [f32; 4] {
+    fn accepts(kind: GLenum) -> bool {
+        kind == gl::FLOAT_VEC4
+    }
+
+    fn upload(&self, location: GLint) {
+        unsafe { gl::Uniform4f(location, self[0], self[1], self[2], self[3]) }
+    }
+}
+
+impl Uniform for [i32; 2] {
+    fn accepts(kind: GLenum) -> bool {
+        kind == gl::INT_VEC2
+    }
+
+    fn upload(&self, location: GLint) {
+        unsafe { gl::Uniform2i(location, self[0], self[1]) }
+    }
+}
+
+impl Uniform for [i32; 3] {
+    fn accepts(kind: GLenum) -> bool {
+        kind == gl::INT_VEC3
+    }
+
+    fn upload(&self, location: GLint) {
+        unsafe { gl::Uniform3i(location, self[0], self[1], self[2]) }
+    }
+}
+
+impl Uniform for [i32; 4] {
+    fn accepts(kind: GLenum) -> bool {
+        kind == gl::INT_VEC4
+    }
+
+    fn upload(&self, location: GLint) {
+        unsafe { gl::Uniform4i(location, self[0], self[1], self[2], self[3]) }
+    }
+}
+
+impl Uniform for [u32; 2] {
+    fn accepts(kind: GLenum) -> bool {
+        kind == gl::UNSIGNED_INT_VEC2
+    }
+
+    fn upload(&self, location: GLint) {
+        unsafe { gl::Uniform2ui(location, self[0], self[1]) }
+    }
+}
+
+impl Uniform for [u32; 3] {
+    fn accepts(kind: GLenum) -> bool {
+        kind == gl::UNSIGNED_INT_VEC3
+    }
+
+    fn upload(&self, location: GLint) {
+        unsafe { gl::Uniform3ui(location, self[0], self[1], self[2]) }
+    }
+}
+
+impl Uniform for [u32; 4] {
+    fn accepts(kind: GLenum) -> bool {
+        kind == gl::UNSIGNED_INT_VEC4
+    }
+
+    fn upload(&self, location: GLint) {
+        unsafe { gl::Uniform4ui(location, self[0], self[1], self[2], self[3]) }
+    }
+}
+
+impl Uniform for Vec3 {
+    fn accepts(kind: GLenum) -> bool {
+        kind == gl::FLOAT_VEC3
+    }
+
+    fn upload(&self, location: GLint) {
+        unsafe { gl::Uniform3f(location, self.x, self.y, self.z) }
+    }
+}
+
+// Column-major, like Mat4.
+impl Uniform for [[f32; 2]; 2] {
+    fn accepts(kind: GLenum) -> bool {
+        kind == gl::FLOAT_MAT2
+    }
+
+    fn upload(&self, location: GLint) {
+        unsafe { gl::UniformMatrix2fv(location, 1, gl::FALSE, self.as_ptr() as *const f32) }
+    }
+}
+
+impl Uniform for [[f32; 3]; 3] {
+    fn accepts(kind: GLenum) -> bool {
+        kind == gl::FLOAT_MAT3
+    }
+
+    fn upload(&self, location: GLint) {
+        unsafe { gl::UniformMatrix3fv(location, 1, gl::FALSE, self.as_ptr() as *const f32) }
+    }
+}
+
+impl Uniform for Mat4 {
+    fn accepts(kind: GLenum) -> bool {
+        kind == gl::FLOAT_MAT4
+    }
+
+    fn upload(&self, location: GLint) {
+        unsafe { gl::UniformMatrix4fv(location, 1, gl::FALSE, self.as_ptr()) }
+    }
+}
+
+// Whole `float[]` arrays, from the location of their first element.
+impl Uniform for [f32] {
+    fn accepts(kind: GLenum) -> bool {
+        kind == gl::FLOAT
+    }
+
+    fn upload(&self, location: GLint) {
+        unsafe { gl::Uniform1fv(location, self.len() as i32, self.as_ptr()) }
+    }
+}
+
+impl Uniform for [i32] {
+    fn accepts(kind: GLenum) -> bool {
+        kind == gl::INT || is_sampler(kind)
+    }
+
+    fn upload(&self, location: GLint) {
+        unsafe { gl::Uniform1iv(location, self.len() as i32, self.as_ptr()) }
+    }
+}
@@ -0,0 +1,69 @@
+use crate::algebra::mat4::Mat4;
+use crate::algebra::vec3::Vec3;
+use crate::render::buffer::Buffer;
+
+pub struct UniformBuffer {
+    buffer: Buffer,
+    size: usize,
+}
+
+impl UniformBuffer {
+    // `size` is in floats; the buffer stays bound to `binding` for every program.
+    pub fn new(size: usize, binding: u32) -> Self {
+        let buffer = Buffer::new(gl::UNIFORM_BUFFER);
+        buffer.allocate(size * size_of::<gl::types::GLfloat>(), gl::DYNAMIC_DRAW);
+        buffer.bind_base(binding);
+        UniformBuffer { buffer, size }
+    }
+
+    pub fn update(&self, data: &[f32]) {
+        self.buffer.update(&data[..data.len().min(self.size)]);
+    }
+}
+
+// Lays values out by the std140 rules: scalars take 4 bytes, vec3, vec4 and matrix columns
+// start on 16 bytes.
+pub struct Std140 {
+    data: Vec<f32>,
+}
+
+impl Std140 {
+    pub fn new() -> Self {
+        Std140 { data: Vec::new() }
+    }
+
+    pub fn float(&mut self, value: f32) -> &mut Self {
+        self.data.push(value);
+        self
+    }
+
+    // GLSL bools are 32-bit integers in a block.
+    pub fn bool(&mut self, value: bool) -> &mut Self {
+        self.data.push(f32::from_bits(value as u32));
+        self
+    }
+
+    pub fn vec3(&mut self, value: &Vec3) -> &mut Self {
+        self.align(4);
+        self.data.extend([value.x, value.y, value.z]);
+        self
+    }
+
+    pub fn mat4(&mut self, value: &Mat4) -> &mut Self {
+        self.align(4);
+        for column in 0..4 {
+            self.data.extend((0..4).map(|row| value.row(row)[column]));
+        }
+        self
+    }
+
+    pub fn as_slice(&self) -> &[f32] {
+        &self.data
+    }
+
+    fn align(&mut self, floats: usize) {
+        while !self.data.len().is_multiple_of(floats) {
+            self.data.push(0.0);
+        }
+    }
+}
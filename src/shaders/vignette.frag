@@ -0,0 +1,15 @@
+#version 330 core
+in vec2 ScreenPos;
+
+out vec4 FragColor;
+
+uniform sampler2D source;
+uniform float strength;
+// Distance from the centre, in half screen heights, where the darkening starts.
+uniform float radius;
+
+void main() {
+    vec3 color = texture(source, ScreenPos * 0.5 + 0.5).rgb;
+    float falloff = smoothstep(radius, radius + 0.6, length(ScreenPos));
+    FragColor = vec4(color * (1.0 - strength * falloff), 1.0);
+}
@@ -0,0 +1,44 @@
+use std::path::Path;
+
+// Decoded RGBA8 pixels, rows top to bottom, ready to hand to any backend.
+pub struct ImageData {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+    // Colour imagery is sRGB encoded and decoded to linear on sampling, data maps are not.
+    pub srgb: bool,
+}
+
+impl ImageData {
+    pub fn load(path: &Path, srgb: bool) -> Result<Self, image::ImageError> {
+        let image = image::open(path)?.into_rgba8();
+        let (width, height) = image.dimensions();
+        Ok(ImageData {
+            width,
+            height,
+            pixels: image.into_raw(),
+            srgb,
+        })
+    }
+
+    // Optional assets: a missing file is not an error, a broken one is reported.
+    pub fn load_optional(path: &Path, srgb: bool) -> Option<Self> {
+        if !path.exists() {
+            return None;
+        }
+
+        ImageData::load(path, srgb)
+            .map_err(|err| eprintln!("Could not load {}: {}", path.display(), err))
+            .ok()
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), image::ImageError> {
+        image::save_buffer(
+            path,
+            &self.pixels,
+            self.width,
+            self.height,
+            image::ExtendedColorType::Rgba8,
+        )
+    }
+}
@@ -0,0 +1,55 @@
+use crate::textures::texture::Texture;
+
+use image::error::{ParameterError, ParameterErrorKind};
+use image::ImageError;
+
+use std::path::Path;
+
+// Colour lookup table for grading, stored on disk as the usual horizontal strip of
+// `size` slices of `size x size` texels, blue increasing from slice to slice.
+pub struct Lut3d {
+    pub texture: Texture,
+    pub size: u32,
+}
+
+impl Lut3d {
+    pub fn load(path: &Path) -> Result<Self, ImageError> {
+        let image = image::open(path)?.into_rgb8();
+        let (width, height) = image.dimensions();
+        let size = height;
+        if width != size * size {
+            return Err(ImageError::Parameter(ParameterError::from_kind(
+                ParameterErrorKind::DimensionMismatch,
+            )));
+        }
+
+        // Reorder the strip so red runs fastest, then green, then blue.
+        let mut texels = Vec::with_capacity((size * size * size * 3) as usize);
+        for b in 0..size {
+            for g in 0..size {
+                for r in 0..size {
+                    texels.extend_from_slice(&image.get_pixel(b * size + r, g).0);
+                }
+            }
+        }
+
+        let texture = Texture::new(gl::TEXTURE_3D);
+        texture.image_3d(
+            gl::RGB8,
+            size,
+            size,
+            size,
+            gl::RGB,
+            gl::UNSIGNED_BYTE,
+            Some(&texels),
+        );
+        texture.set_filter(gl::LINEAR, gl::LINEAR);
+        texture.set_wrap(gl::CLAMP_TO_EDGE);
+
+        Ok(Lut3d { texture, size })
+    }
+
+    pub fn bind(&self, unit: u32) {
+        self.texture.bind(unit);
+    }
+}
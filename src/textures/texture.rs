@@ -0,0 +1,193 @@
+use crate::render::buffer::Buffer;
+use crate::textures::image::ImageData;
+
+use gl::types::{GLenum, GLuint};
+
+use std::path::Path;
+
+// The last of the 16 fragment texture units OpenGL 3.3 guarantees, never sampled from.
+const SCRATCH_UNIT: u32 = 15;
+
+// A GL texture object, deleted on drop. `target` is its kind, e.g. gl::TEXTURE_2D.
+pub struct Texture {
+    id: GLuint,
+    target: GLenum,
+}
+
+impl Texture {
+    pub fn new(target: GLenum) -> Self {
+        let mut id = 0;
+        unsafe {
+            gl::GenTextures(1, &mut id);
+        }
+        Texture { id, target }
+    }
+
+    // Mipmapped, repeating around the globe's longitude and clamped at the poles.
+    pub fn from_image(image: &ImageData) -> Self {
+        let texture = Texture::new(gl::TEXTURE_2D);
+        texture.image_2d(
+            if image.srgb {
+                gl::SRGB8_ALPHA8
+            } else {
+                gl::RGBA8
+            },
+            image.width,
+            image.height,
+            gl::RGBA,
+            gl::UNSIGNED_BYTE,
+            Some(&image.pixels),
+        );
+        texture.generate_mipmaps();
+        texture.set_parameter(gl::TEXTURE_WRAP_S, gl::REPEAT);
+        texture.set_parameter(gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE);
+        texture.set_filter(gl::LINEAR_MIPMAP_LINEAR, gl::LINEAR);
+        texture
+    }
+
+    pub fn load_optional(path: &Path) -> Option<Self> {
+        ImageData::load_optional(path, false).map(|image| Texture::from_image(&image))
+    }
+
+    pub fn load_optional_srgb(path: &Path) -> Option<Self> {
+        ImageData::load_optional(path, true).map(|image| Texture::from_image(&image))
+    }
+
+    pub fn id(&self) -> GLuint {
+        self.id
+    }
+
+    // Level 0 of a 2D texture, left undefined without `pixels`. Rows are tightly packed.
+    pub fn image_2d(
+        &self,
+        internal_format: GLenum,
+        width: u32,
+        height: u32,
+        format: GLenum,
+        kind: GLenum,
+        pixels: Option<&[u8]>,
+    ) {
+        self.bind_scratch();
+        unsafe {
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, 1);
+            gl::TexImage2D(
+                self.target,
+                0,
+                internal_format as i32,
+                width as i32,
+                height as i32,
+                0,
+                format,
+                kind,
+                pixels.map_or(std::ptr::null(), |pixels| pixels.as_ptr() as *const _),
+            );
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, 4);
+        }
+    }
+
+    // Level 0 of a 3D texture or 2D array, `depth` being the slice or layer count.
+    #[allow(clippy::too_many_arguments)]
+    pub fn image_3d(
+        &self,
+        internal_format: GLenum,
+        width: u32,
+        height: u32,
+        depth: u32,
+        format: GLenum,
+        kind: GLenum,
+        pixels: Option<&[u8]>,
+    ) {
+        self.bind_scratch();
+        unsafe {
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, 1);
+            gl::TexImage3D(
+                self.target,
+                0,
+                internal_format as i32,
+                width as i32,
+                height as i32,
+                depth as i32,
+                0,
+                format,
+                kind,
+                pixels.map_or(std::ptr::null(), |pixels| pixels.as_ptr() as *const _),
+            );
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, 4);
+        }
+    }
+
+    pub fn set_parameter(&self, name: GLenum, value: GLenum) {
+        self.bind_scratch();
+        unsafe {
+            gl::TexParameteri(self.target, name, value as i32);
+        }
+    }
+
+    pub fn set_filter(&self, min: GLenum, mag: GLenum) {
+        self.set_parameter(gl::TEXTURE_MIN_FILTER, min);
+        self.set_parameter(gl::TEXTURE_MAG_FILTER, mag);
+    }
+
+    // Same wrap mode on every axis the texture has.
+    pub fn set_wrap(&self, wrap: GLenum) {
+        self.set_parameter(gl::TEXTURE_WRAP_S, wrap);
+        self.set_parameter(gl::TEXTURE_WRAP_T, wrap);
+        if self.target == gl::TEXTURE_3D {
+            self.set_parameter(gl::TEXTURE_WRAP_R, wrap);
+        }
+    }
+
+    pub fn set_border_color(&self, color: [f32; 4]) {
+        self.bind_scratch();
+        unsafe {
+            gl::TexParameterfv(self.target, gl::TEXTURE_BORDER_COLOR, color.as_ptr());
+        }
+    }
+
+    // Tiled textures wrap in both directions instead of clamping at the poles.
+    pub fn set_repeat(&self) {
+        self.set_wrap(gl::REPEAT);
+    }
+
+    pub fn generate_mipmaps(&self) {
+        self.bind_scratch();
+        unsafe {
+            gl::GenerateMipmap(self.target);
+        }
+    }
+
+    // Starts copying mip `level` as float `format` texels to the start of `buffer`, a pixel
+    // pack buffer, without waiting for the GPU.
+    pub fn pack_level(&self, level: i32, format: GLenum, buffer: &Buffer) {
+        self.bind_scratch();
+        buffer.bind();
+        unsafe {
+            gl::GetTexImage(self.target, level, format, gl::FLOAT, std::ptr::null_mut());
+        }
+        buffer.unbind();
+    }
+
+    pub fn bind(&self, unit: u32) {
+        unsafe {
+            gl::ActiveTexture(gl::TEXTURE0 + unit);
+            gl::BindTexture(self.target, self.id);
+        }
+    }
+
+    // Setup calls bind on a unit of their own, so configuring a texture mid-frame leaves
+    // whatever the current pass has bound to its units alone.
+    fn bind_scratch(&self) {
+        unsafe {
+            gl::ActiveTexture(gl::TEXTURE0 + SCRATCH_UNIT);
+            gl::BindTexture(self.target, self.id);
+        }
+    }
+}
+
+impl Drop for Texture {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteTextures(1, &self.id);
+        }
+    }
+}